@@ -0,0 +1,88 @@
+use crate::jj::Jj;
+use std::collections::HashMap;
+
+/// jjt settings, read from jj config under the `jjt.` prefix
+/// (e.g. `jj config set --repo jjt.claim.exclusive false`).
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    /// Load all `jjt.*` settings. Missing or unreadable config is treated as empty.
+    pub fn load() -> Config {
+        match Jj::config_list("jjt") {
+            Ok(out) => Config::parse(&out),
+            Err(_) => Config::default(),
+        }
+    }
+
+    /// Parse `jj config list` output: one `key = value` line per setting.
+    pub fn parse(out: &str) -> Config {
+        let mut values = HashMap::new();
+        for line in out.lines() {
+            let Some((key, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            // Keys with special characters are quoted per segment: jjt.wip."a b"
+            let key = key.trim().replace('"', "");
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .map(|v| v.replace("\\\"", "\"").replace("\\\\", "\\"))
+                .unwrap_or_else(|| value.to_string());
+            values.insert(key, value);
+        }
+        Config { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn get_u32(&self, key: &str) -> Option<u32> {
+        self.get(key).and_then(|v| v.parse().ok())
+    }
+
+    /// Settings under `prefix.`, as (rest of key, value) pairs.
+    pub fn entries<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let prefix = format!("{prefix}.");
+        self.values
+            .iter()
+            .filter_map(move |(k, v)| k.strip_prefix(&prefix).map(|rest| (rest, v.as_str())))
+    }
+
+    pub fn get_bool(&self, key: &str, default: bool) -> bool {
+        match self.get(key) {
+            Some("true") => true,
+            Some("false") => false,
+            _ => default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_output() {
+        let out = "\
+jjt.claim.exclusive = false
+jjt.name = \"say \\\"hi\\\"\"
+jjt.wip.agents.\"Jane Doe\" = 2
+jjt.wip.agents.\"ci@example.com\" = 5
+jjt.wip.agent = 3
+";
+        let config = Config::parse(out);
+        assert!(!config.get_bool("jjt.claim.exclusive", true));
+        assert_eq!(config.get("jjt.name"), Some("say \"hi\""));
+        assert_eq!(config.get("jjt.wip.agents.Jane Doe"), Some("2"));
+        assert_eq!(config.get_u32("jjt.wip.agent"), Some(3));
+        let mut agents: Vec<_> = config.entries("jjt.wip.agents").collect();
+        agents.sort();
+        assert_eq!(agents, vec![("Jane Doe", "2"), ("ci@example.com", "5")]);
+        assert!(config.get_bool("jjt.missing", true));
+    }
+}
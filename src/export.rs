@@ -0,0 +1,196 @@
+use crate::task::{Status, Task};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Tasks to draw and how to draw them. Edges are only drawn between tasks in
+/// the selection, so a filtered graph never points at nodes it doesn't show.
+pub struct Board<'a> {
+    pub tasks: Vec<&'a Task>,
+    pub blocked: &'a HashSet<String>,
+    /// Also draw each task's linked jj change as its own node.
+    pub changes: bool,
+}
+
+impl Board<'_> {
+    /// Node class: the task's status, or "blocked" for open tasks still waiting.
+    fn class(&self, task: &Task) -> &'static str {
+        match task.status {
+            Status::Open if self.blocked.contains(&task.id) => "blocked",
+            Status::Open => "open",
+            Status::Claimed => "claimed",
+            Status::Done => "done",
+        }
+    }
+
+    fn shown(&self, id: &str) -> bool {
+        self.tasks.iter().any(|t| t.id == id)
+    }
+
+    /// (blocker, blocked) pairs.
+    fn block_edges(&self) -> Vec<(&str, &str)> {
+        self.tasks
+            .iter()
+            .flat_map(|t| {
+                t.blocked_by
+                    .iter()
+                    .filter(|d| self.shown(d))
+                    .map(|d| (d.as_str(), t.id.as_str()))
+            })
+            .collect()
+    }
+
+    /// (earlier, later) pairs from soft dependencies.
+    fn after_edges(&self) -> Vec<(&str, &str)> {
+        self.tasks
+            .iter()
+            .flat_map(|t| {
+                t.after
+                    .iter()
+                    .filter(|a| self.shown(a))
+                    .map(|a| (a.as_str(), t.id.as_str()))
+            })
+            .collect()
+    }
+
+    /// (task, target, kind) triples.
+    fn link_edges(&self) -> Vec<(&str, &str, String)> {
+        self.tasks
+            .iter()
+            .flat_map(|t| {
+                t.links
+                    .iter()
+                    .filter(|l| self.shown(&l.target))
+                    .map(|l| (t.id.as_str(), l.target.as_str(), l.kind.to_string()))
+            })
+            .collect()
+    }
+
+    /// Graphviz DOT.
+    pub fn dot(&self) -> String {
+        let mut out = String::from("digraph jjt {\n  rankdir=LR;\n");
+        out.push_str("  node [shape=box, style=\"rounded,filled\", fillcolor=white];\n");
+        for t in &self.tasks {
+            let (fill, font) = match self.class(t) {
+                "claimed" => ("#cfe8ff", "black"),
+                "blocked" => ("#ffe0b2", "black"),
+                "done" => ("#dddddd", "#777777"),
+                _ => ("white", "black"),
+            };
+            let label = dot_escape(&format!("{}\n{}", t.id, t.summary));
+            let _ = writeln!(
+                out,
+                "  t_{} [label=\"{label}\", fillcolor=\"{fill}\", fontcolor=\"{font}\"];",
+                t.id
+            );
+        }
+        for (from, to) in self.block_edges() {
+            let _ = writeln!(out, "  t_{from} -> t_{to};");
+        }
+        for (from, to) in self.after_edges() {
+            let _ = writeln!(out, "  t_{from} -> t_{to} [style=dotted, label=\"after\"];");
+        }
+        for (from, to, kind) in self.link_edges() {
+            let _ = writeln!(
+                out,
+                "  t_{from} -> t_{to} [style=dashed, label=\"{kind}\"];"
+            );
+        }
+        if self.changes {
+            for t in &self.tasks {
+                if let Some(ref change) = t.change {
+                    let _ = writeln!(
+                        out,
+                        "  c_{change} [label=\"{change}\", shape=note, fillcolor=\"#eeeeee\"];"
+                    );
+                    let _ = writeln!(out, "  t_{} -> c_{change} [style=dotted];", t.id);
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart, e.g. for a fenced ```mermaid block in Markdown.
+    pub fn mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+        for t in &self.tasks {
+            let label = mermaid_escape(&format!("{}: {}", t.id, t.summary));
+            let _ = writeln!(out, "  t_{}[\"{label}\"]:::{}", t.id, self.class(t));
+        }
+        for (from, to) in self.block_edges() {
+            let _ = writeln!(out, "  t_{from} --> t_{to}");
+        }
+        for (from, to) in self.after_edges() {
+            let _ = writeln!(out, "  t_{from} -.->|after| t_{to}");
+        }
+        for (from, to, kind) in self.link_edges() {
+            let _ = writeln!(out, "  t_{from} -. {kind} .-> t_{to}");
+        }
+        if self.changes {
+            for t in &self.tasks {
+                if let Some(ref change) = t.change {
+                    let _ = writeln!(out, "  c_{change}[/\"{change}\"/]:::change");
+                    let _ = writeln!(out, "  t_{} -.- c_{change}", t.id);
+                }
+            }
+        }
+        out.push_str("  classDef open fill:#fff,stroke:#333\n");
+        out.push_str("  classDef claimed fill:#cfe8ff,stroke:#333\n");
+        out.push_str("  classDef blocked fill:#ffe0b2,stroke:#333\n");
+        out.push_str("  classDef done fill:#ddd,stroke:#999,color:#777\n");
+        out.push_str("  classDef change fill:#eee,stroke:#999\n");
+        out
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_nodes_and_edges() {
+        let a = Task::from_description(
+            "aaa".into(),
+            "jjt: Ship \"v2\"\nstatus: open\npriority: 2\nblocked_by: bbb zzz\nlinks: bbb/relates_to\n",
+        )
+        .unwrap();
+        let b = Task::from_description(
+            "bbb".into(),
+            "jjt: Prep\nstatus: claimed\npriority: 2\nagent: claude\nchange: kkk\nafter: aaa\n",
+        )
+        .unwrap();
+        let blocked = HashSet::from(["aaa".to_string()]);
+        let board = Board {
+            tasks: vec![&a, &b],
+            blocked: &blocked,
+            changes: true,
+        };
+
+        let dot = board.dot();
+        assert!(dot.contains(r##"t_aaa [label="aaa\nShip \"v2\"", fillcolor="#ffe0b2""##));
+        assert!(dot.contains("  t_bbb -> t_aaa;\n"));
+        assert!(!dot.contains("zzz")); // not in the selection
+        assert!(dot.contains(r#"t_aaa -> t_bbb [style=dashed, label="relates_to"];"#));
+        assert!(dot.contains("t_bbb -> c_kkk [style=dotted];"));
+        assert!(dot.contains(r#"t_aaa -> t_bbb [style=dotted, label="after"];"#));
+
+        let mermaid = board.mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("t_aaa[\"aaa: Ship #quot;v2#quot;\"]:::blocked"));
+        assert!(mermaid.contains("t_bbb[\"bbb: Prep\"]:::claimed"));
+        assert!(mermaid.contains("  t_bbb --> t_aaa\n"));
+        assert!(mermaid.contains("  t_aaa -. relates_to .-> t_bbb\n"));
+        assert!(mermaid.contains("  t_bbb -.- c_kkk\n"));
+        assert!(mermaid.contains("  t_aaa -.->|after| t_bbb\n"));
+    }
+}
@@ -0,0 +1,357 @@
+use crate::task::{Status, Task};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The `blocked_by` graph: each task points at the tasks blocking it.
+pub struct DepGraph<'a> {
+    blockers: HashMap<&'a str, Vec<&'a str>>,
+    dependents: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> DepGraph<'a> {
+    pub fn from_tasks(tasks: impl IntoIterator<Item = &'a Task>) -> DepGraph<'a> {
+        let mut blockers = HashMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for t in tasks {
+            let deps: Vec<&str> = t.blocked_by.iter().map(String::as_str).collect();
+            for &dep in &deps {
+                dependents.entry(dep).or_default().push(t.id.as_str());
+            }
+            blockers.insert(t.id.as_str(), deps);
+        }
+        DepGraph {
+            blockers,
+            dependents,
+        }
+    }
+
+    /// Everything `id` waits on, directly or transitively, as (task, depth)
+    /// pairs in breadth-first order. Direct blockers have depth 1.
+    pub fn upstream(&self, id: &'a str) -> Vec<(&'a str, usize)> {
+        Self::closure(&self.blockers, id)
+    }
+
+    /// Everything waiting on `id`, directly or transitively, as (task, depth) pairs.
+    pub fn downstream(&self, id: &'a str) -> Vec<(&'a str, usize)> {
+        Self::closure(&self.dependents, id)
+    }
+
+    fn closure(edges: &HashMap<&'a str, Vec<&'a str>>, id: &'a str) -> Vec<(&'a str, usize)> {
+        let mut out = Vec::new();
+        let mut queue = VecDeque::from([(id, 0)]);
+        let mut seen = HashSet::from([id]);
+        while let Some((node, depth)) = queue.pop_front() {
+            for &next in edges.get(node).into_iter().flatten() {
+                if seen.insert(next) {
+                    out.push((next, depth + 1));
+                    queue.push_back((next, depth + 1));
+                }
+            }
+        }
+        out
+    }
+
+    /// The heaviest chain of blockers ending at `to` (or anywhere, if `None`),
+    /// in the order the tasks must be done, with its total weight. Only blockers
+    /// that are themselves in the graph count, so build it from unfinished tasks.
+    pub fn critical_path(
+        &self,
+        to: Option<&'a str>,
+        weight: impl Fn(&str) -> u32,
+    ) -> (Vec<&'a str>, u32) {
+        let mut memo: HashMap<&str, (u32, Option<&str>)> = HashMap::new();
+        let mut ends: Vec<&str> = match to {
+            Some(id) => vec![id],
+            None => self.blockers.keys().copied().collect(),
+        };
+        ends.sort();
+
+        let mut best: Option<(&str, u32)> = None;
+        for end in ends {
+            let mut stack = Vec::new();
+            let total = self.heaviest(end, &weight, &mut memo, &mut stack);
+            if best.is_none_or(|(_, b)| total > b) {
+                best = Some((end, total));
+            }
+        }
+        let Some((end, total)) = best else {
+            return (vec![], 0);
+        };
+        let mut path = vec![end];
+        let mut cur = end;
+        while let Some(&(_, Some(next))) = memo.get(cur) {
+            path.push(next);
+            cur = next;
+        }
+        path.reverse();
+        (path, total)
+    }
+
+    fn heaviest(
+        &self,
+        node: &'a str,
+        weight: &impl Fn(&str) -> u32,
+        memo: &mut HashMap<&'a str, (u32, Option<&'a str>)>,
+        stack: &mut Vec<&'a str>,
+    ) -> u32 {
+        if let Some(&(total, _)) = memo.get(node) {
+            return total;
+        }
+        stack.push(node);
+        let mut best: Option<(&str, u32)> = None;
+        for &next in self.blockers.get(node).into_iter().flatten() {
+            // Blockers off the graph are finished; ones on the stack close a cycle.
+            if !self.blockers.contains_key(next) || stack.contains(&next) {
+                continue;
+            }
+            let total = self.heaviest(next, weight, memo, stack);
+            if best.is_none_or(|(_, b)| total > b) {
+                best = Some((next, total));
+            }
+        }
+        stack.pop();
+        let total = weight(node) + best.map_or(0, |(_, b)| b);
+        memo.insert(node, (total, best.map(|(n, _)| n)));
+        total
+    }
+
+    /// Shortest chain `from` -> ... -> `to` following blocked_by edges.
+    pub fn path(&self, from: &'a str, to: &'a str) -> Option<Vec<&'a str>> {
+        let mut prev: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut seen = HashSet::from([from]);
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                let mut cur = to;
+                while let Some(&p) = prev.get(cur) {
+                    path.push(p);
+                    cur = p;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &next in self.blockers.get(node).into_iter().flatten() {
+                if seen.insert(next) {
+                    prev.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// The cycle that adding "`task` blocked by `blocker`" would close, as
+    /// `task -> blocker -> ... -> task`.
+    pub fn cycle_if_blocked(&self, task: &'a str, blocker: &'a str) -> Option<Vec<&'a str>> {
+        let mut cycle = self.path(blocker, task)?;
+        cycle.insert(0, task);
+        Some(cycle)
+    }
+
+    /// Cycles found by a depth-first walk: at least one through every group of
+    /// mutually blocking tasks. Each starts at its smallest ID and ends where it started.
+    pub fn cycles(&self) -> Vec<Vec<&'a str>> {
+        let mut nodes: Vec<&str> = self.blockers.keys().copied().collect();
+        nodes.sort();
+
+        let mut found: HashSet<Vec<&str>> = HashSet::new();
+        let mut done: HashSet<&str> = HashSet::new();
+        for &start in &nodes {
+            let mut stack: Vec<&str> = Vec::new();
+            self.visit(start, &mut stack, &mut done, &mut found);
+        }
+
+        let mut cycles: Vec<Vec<&str>> = found.into_iter().collect();
+        cycles.sort();
+        cycles
+    }
+
+    fn visit(
+        &self,
+        node: &'a str,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        found: &mut HashSet<Vec<&'a str>>,
+    ) {
+        if let Some(pos) = stack.iter().position(|&n| n == node) {
+            let mut cycle: Vec<&str> = stack[pos..].to_vec();
+            let min = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+            cycle.rotate_left(min);
+            cycle.push(cycle[0]);
+            found.insert(cycle);
+            return;
+        }
+        if done.contains(node) {
+            return;
+        }
+        stack.push(node);
+        for &next in self.blockers.get(node).into_iter().flatten() {
+            self.visit(next, stack, done, found);
+        }
+        stack.pop();
+        done.insert(node);
+    }
+}
+
+/// Order `candidates` (already sorted by priority, then preference) so that
+/// each task comes after its unfinished soft dependencies (`after`). Soft
+/// dependencies are hints, so a task only moves within its priority band:
+/// behind band-mates it follows, and to the end of the band when it follows an
+/// unfinished task not yet placed. Soft cycles fall back to preference order.
+pub fn schedule<'a>(candidates: Vec<&'a Task>, tasks: &[Task]) -> Vec<&'a Task> {
+    let unfinished: HashSet<&str> = tasks
+        .iter()
+        .filter(|t| t.status != Status::Done)
+        .map(|t| t.id.as_str())
+        .collect();
+    let mut out: Vec<&Task> = Vec::with_capacity(candidates.len());
+    for band in candidates.chunk_by(|a, b| a.priority == b.priority) {
+        let in_band: HashSet<&str> = band.iter().map(|t| t.id.as_str()).collect();
+        let placed: HashSet<&str> = out.iter().map(|t| t.id.as_str()).collect();
+        let waits_outside = |t: &Task| {
+            t.after.iter().any(|a| {
+                let a = a.as_str();
+                unfinished.contains(a) && !in_band.contains(a) && !placed.contains(a)
+            })
+        };
+        let (mut remaining, deferred): (Vec<&Task>, Vec<&Task>) =
+            band.iter().partition(|t| !waits_outside(t));
+
+        while !remaining.is_empty() {
+            let pending: HashSet<&str> = remaining.iter().map(|t| t.id.as_str()).collect();
+            let pos = remaining
+                .iter()
+                .position(|t| !t.after.iter().any(|a| pending.contains(a.as_str())))
+                .unwrap_or(0);
+            out.push(remaining.remove(pos));
+        }
+        out.extend(deferred);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, blocked_by: &[&str]) -> Task {
+        let desc = format!(
+            "jjt: {id}\nstatus: open\npriority: 2\nblocked_by: {}\n",
+            blocked_by.join(" ")
+        );
+        Task::from_description(id.into(), &desc).unwrap()
+    }
+
+    #[test]
+    fn rejects_edge_closing_a_cycle() {
+        let tasks = vec![task("a", &["b"]), task("b", &["c"]), task("c", &[])];
+        let graph = DepGraph::from_tasks(&tasks);
+        assert_eq!(
+            graph.cycle_if_blocked("c", "a"),
+            Some(vec!["c", "a", "b", "c"])
+        );
+        assert_eq!(graph.cycle_if_blocked("a", "c"), None);
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn transitive_closures() {
+        let tasks = vec![
+            task("a", &["b", "c"]),
+            task("b", &["d"]),
+            task("c", &["d"]),
+            task("d", &[]),
+            task("e", &["a"]),
+        ];
+        let graph = DepGraph::from_tasks(&tasks);
+        assert_eq!(graph.upstream("a"), vec![("b", 1), ("c", 1), ("d", 2)]);
+        let mut down = graph.downstream("d");
+        down.sort();
+        assert_eq!(down, vec![("a", 2), ("b", 1), ("c", 1), ("e", 3)]);
+        assert!(graph.downstream("e").is_empty());
+    }
+
+    #[test]
+    fn critical_path_is_weighted() {
+        let tasks = vec![
+            task("a", &["b", "c"]),
+            task("b", &["d"]),
+            task("c", &[]),
+            task("d", &["done"]),
+        ];
+        let graph = DepGraph::from_tasks(&tasks);
+        let unit = |_: &str| 1;
+        assert_eq!(
+            graph.critical_path(Some("a"), unit),
+            (vec!["d", "b", "a"], 3)
+        );
+        let heavy_c = |id: &str| if id == "c" { 5 } else { 1 };
+        assert_eq!(graph.critical_path(Some("a"), heavy_c), (vec!["c", "a"], 6));
+        assert_eq!(graph.critical_path(None, heavy_c), (vec!["c", "a"], 6));
+        assert_eq!(graph.critical_path(Some("d"), unit), (vec!["d"], 1));
+    }
+
+    #[test]
+    fn schedule_respects_soft_dependencies() {
+        let soft = |id: &str, after: &str, status: &str| {
+            let desc = format!("jjt: {id}\nstatus: {status}\npriority: 2\nafter: {after}\n");
+            Task::from_description(id.into(), &desc).unwrap()
+        };
+        let tasks = vec![
+            soft("a", "b", "open"), // should follow b
+            soft("b", "", "open"),
+            soft("c", "x", "open"),    // waits on x, which isn't a candidate
+            soft("d", "done", "open"), // soft dependency already finished
+            soft("x", "", "claimed"),
+            soft("done", "", "done"),
+        ];
+        let candidates: Vec<&Task> = tasks.iter().take(4).collect();
+        let order: Vec<&str> = schedule(candidates, &tasks)
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(order, vec!["b", "a", "d", "c"]);
+    }
+
+    #[test]
+    fn schedule_keeps_priority_over_soft_dependencies() {
+        let soft = |id: &str, priority: u8, after: &str, status: &str| {
+            let desc =
+                format!("jjt: {id}\nstatus: {status}\npriority: {priority}\nafter: {after}\n");
+            Task::from_description(id.into(), &desc).unwrap()
+        };
+        let tasks = vec![
+            soft("urgent", 0, "x", "open"), // softly after claimed x, still first
+            soft("a", 1, "b", "open"),      // moves behind b within p1
+            soft("b", 1, "", "open"),
+            soft("c", 1, "x", "open"), // end of p1, not behind p3
+            soft("d", 3, "", "open"),
+            soft("e", 3, "late", "open"), // waits on claimed `late`: end of p3
+            soft("x", 0, "", "claimed"),
+            soft("late", 0, "", "claimed"),
+        ];
+        let candidates: Vec<&Task> = tasks.iter().take(6).collect();
+        let order: Vec<&str> = schedule(candidates, &tasks)
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(order, vec!["urgent", "b", "a", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn finds_existing_cycles() {
+        let tasks = vec![
+            task("a", &["b"]),
+            task("b", &["a"]),
+            task("c", &["d"]),
+            task("d", &["e"]),
+            task("e", &["c"]),
+            task("f", &["a"]),
+        ];
+        let graph = DepGraph::from_tasks(&tasks);
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["a", "b", "a"], vec!["c", "d", "e", "c"]]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+
+/// An agent identity as stored on claims and notes: "Name <email>", or a bare
+/// name for tasks written before identities carried an email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+impl Identity {
+    pub fn parse(s: &str) -> Identity {
+        let s = s.trim();
+        if let Some((name, rest)) = s.split_once('<') {
+            if let Some(email) = rest.strip_suffix('>') {
+                return Identity {
+                    name: name.trim().to_string(),
+                    email: Some(email.trim().to_string()),
+                };
+            }
+        }
+        if s.contains('@') && !s.contains(' ') {
+            return Identity {
+                name: String::new(),
+                email: Some(s.to_string()),
+            };
+        }
+        Identity {
+            name: s.to_string(),
+            email: None,
+        }
+    }
+
+    /// Whether a stored identity refers to the same agent, matching on either
+    /// name or email so bare names from older tasks keep matching.
+    pub fn matches(&self, stored: &str) -> bool {
+        let other = Identity::parse(stored);
+        let same_name = !self.name.is_empty() && self.name == other.name;
+        let same_email = match (&self.email, &other.email) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => false,
+        };
+        same_name || same_email
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.email, self.name.is_empty()) {
+            (Some(email), true) => write!(f, "{email}"),
+            (Some(email), false) => write!(f, "{} <{email}>", self.name),
+            (None, _) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let id = Identity::parse("Jane Doe <jane@example.com>");
+        assert_eq!(id.name, "Jane Doe");
+        assert_eq!(id.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(id.to_string(), "Jane Doe <jane@example.com>");
+        assert_eq!(Identity::parse("claude").to_string(), "claude");
+        assert_eq!(Identity::parse("ci@example.com").name, "");
+    }
+
+    #[test]
+    fn matches_name_or_email() {
+        let me = Identity::parse("claude <claude@example.com>");
+        assert!(me.matches("claude"));
+        assert!(me.matches("Claude Bot <CLAUDE@example.com>"));
+        assert!(me.matches("claude@example.com"));
+        assert!(!me.matches("pehle"));
+        assert!(!me.matches("pehle <pehle@example.com>"));
+    }
+}
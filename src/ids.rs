@@ -0,0 +1,108 @@
+//! Task IDs are 12-character jj change IDs. They are matched by prefix among
+//! task commits only, so a short prefix never lands on an unrelated code commit.
+
+use std::collections::HashMap;
+
+/// Outcome of matching a prefix against task IDs.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Match<'a> {
+    One(&'a str),
+    Ambiguous(Vec<&'a str>),
+    None,
+}
+
+/// The task ID `spec` abbreviates. An exact ID wins over longer ones it prefixes.
+pub fn match_prefix<'a>(spec: &str, ids: impl IntoIterator<Item = &'a str>) -> Match<'a> {
+    if spec.is_empty() {
+        return Match::None;
+    }
+    let mut found: Vec<&str> = ids.into_iter().filter(|id| id.starts_with(spec)).collect();
+    if let Some(&exact) = found.iter().find(|&&id| id == spec) {
+        return Match::One(exact);
+    }
+    found.sort_unstable();
+    found.dedup();
+    match found.len() {
+        0 => Match::None,
+        1 => Match::One(found[0]),
+        _ => Match::Ambiguous(found),
+    }
+}
+
+/// The shortest prefix that tells each task ID apart from the rest of the board.
+pub struct Prefixes {
+    lens: HashMap<String, usize>,
+    /// Highlight prefixes with ANSI colors, as jj does on a terminal.
+    color: bool,
+}
+
+impl Prefixes {
+    pub fn new<'a>(ids: impl IntoIterator<Item = &'a str>, color: bool) -> Prefixes {
+        let mut ids: Vec<&str> = ids.into_iter().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        // In sorted order, an ID shares its longest prefix with a neighbour.
+        let common =
+            |a: &str, b: &str| a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+        let lens = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let before = i.checked_sub(1).map_or(0, |j| common(ids[j], id));
+                let after = ids.get(i + 1).map_or(0, |next| common(id, next));
+                (id.to_string(), (before.max(after) + 1).min(id.len()))
+            })
+            .collect();
+        Prefixes { lens, color }
+    }
+
+    /// Length of `id`'s shortest unique prefix (the whole ID if unknown).
+    pub fn len(&self, id: &str) -> usize {
+        self.lens.get(id).copied().unwrap_or(id.len())
+    }
+
+    /// `id` with its unique prefix highlighted, padded with spaces to `width`.
+    pub fn show(&self, id: &str, width: usize) -> String {
+        let pad = " ".repeat(width.saturating_sub(id.chars().count()));
+        if !self.color {
+            return format!("{id}{pad}");
+        }
+        let (prefix, rest) = id.split_at(self.len(id).min(id.len()));
+        format!("\x1b[1;35m{prefix}\x1b[0m\x1b[90m{rest}\x1b[0m{pad}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_only_task_ids() {
+        let ids = ["kxqpmnwz", "kxqrstuv", "zyxwvuts"];
+        assert_eq!(match_prefix("z", ids), Match::One("zyxwvuts"));
+        assert_eq!(match_prefix("kxqr", ids), Match::One("kxqrstuv"));
+        assert_eq!(
+            match_prefix("kxq", ids),
+            Match::Ambiguous(vec!["kxqpmnwz", "kxqrstuv"])
+        );
+        assert_eq!(match_prefix("@", ids), Match::None);
+        assert_eq!(match_prefix("", ids), Match::None);
+        assert_eq!(match_prefix("kx", ["kx", "kxy"]), Match::One("kx"));
+    }
+
+    #[test]
+    fn shortest_unique_prefixes() {
+        let ids = Prefixes::new(["kxqpmnwz", "kxqrstuv", "zyxwvuts"], false);
+        assert_eq!(ids.len("kxqpmnwz"), 4);
+        assert_eq!(ids.len("kxqrstuv"), 4);
+        assert_eq!(ids.len("zyxwvuts"), 1);
+        assert_eq!(ids.len("unknown"), 7);
+        assert_eq!(ids.show("zyxwvuts", 10), "zyxwvuts  ");
+
+        let colored = Prefixes::new(["kxqpmnwz", "zyxwvuts"], true);
+        assert_eq!(
+            colored.show("zyxwvuts", 0),
+            "\x1b[1;35mz\x1b[0m\x1b[90myxwvuts\x1b[0m"
+        );
+    }
+}
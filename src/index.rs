@@ -0,0 +1,49 @@
+use crate::jj::Jj;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Bumped whenever the cached `Task` shape changes, so old caches are ignored.
+const VERSION: u32 = 1;
+
+/// Parsed tasks cached as `.jj/repo/jjt-index.json`. Every jjt or jj write is
+/// a new operation, so an index is only valid for the operation it was built at.
+#[derive(Serialize, Deserialize)]
+struct TaskIndex {
+    version: u32,
+    op_id: String,
+    tasks: Vec<Task>,
+}
+
+fn path() -> Option<PathBuf> {
+    Jj::repo_dir().ok().map(|dir| dir.join("jjt-index.json"))
+}
+
+/// The cached tasks, if the index was built at operation `op_id`.
+pub fn load(op_id: &str) -> Option<Vec<Task>> {
+    let data = fs::read(path()?).ok()?;
+    let index: TaskIndex = serde_json::from_slice(&data).ok()?;
+    (index.version == VERSION && index.op_id == op_id).then_some(index.tasks)
+}
+
+/// Cache `tasks` as the board at operation `op_id`. Best effort: the index is
+/// only an optimisation, so failures are ignored.
+pub fn store(op_id: &str, tasks: &[Task]) {
+    let Some(path) = path() else {
+        return;
+    };
+    let index = TaskIndex {
+        version: VERSION,
+        op_id: op_id.to_string(),
+        tasks: tasks.to_vec(),
+    };
+    let Ok(data) = serde_json::to_vec(&index) else {
+        return;
+    };
+    // Write then rename, so concurrent readers never see a partial file.
+    let tmp = path.with_extension(format!("json.{}", std::process::id()));
+    if fs::write(&tmp, data).is_ok() && fs::rename(&tmp, &path).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
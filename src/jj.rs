@@ -1,14 +1,36 @@
+use crate::identity::Identity;
 use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::PathBuf;
 use std::process::Command;
 
 pub struct Jj;
 
+/// A task commit as read from jj: its description plus commit metadata.
+pub struct TaskRecord {
+    pub change_id: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub created_at: String, // author timestamp, RFC 3339
+    pub updated_at: String, // committer timestamp, RFC 3339
+    pub commit_id: String,
+    pub description: String,
+}
+
+/// Header fields for a task record, tab-separated.
+const TASK_RECORD_HEADER: &str = concat!(
+    r#"change_id.short(12) ++ "\t" ++ author.name() ++ "\t" ++ author.email()"#,
+    r#" ++ "\t" ++ author.timestamp().format("%Y-%m-%dT%H:%M:%S%:z")"#,
+    r#" ++ "\t" ++ committer.timestamp().format("%Y-%m-%dT%H:%M:%S%:z")"#,
+    r#" ++ "\t" ++ commit_id.short(12)"#,
+);
+
 impl Jj {
     fn run(args: &[&str]) -> Result<(String, String)> {
         let out = Command::new("jj")
             .args(args)
             .output()
-            .with_context(|| format!("jj not found — is it installed?"))?;
+            .context("jj not found — is it installed?")?;
         let stdout = String::from_utf8_lossy(&out.stdout).to_string();
         let stderr = String::from_utf8_lossy(&out.stderr).to_string();
         if !out.status.success() {
@@ -26,6 +48,63 @@ impl Jj {
         Ok(())
     }
 
+    /// Path of the current workspace root.
+    pub fn root() -> Result<String> {
+        Self::stdout(&["root"])
+    }
+
+    /// The repo's `.jj/repo` directory, shared by all workspaces.
+    pub fn repo_dir() -> Result<PathBuf> {
+        let repo = PathBuf::from(Self::root()?).join(".jj").join("repo");
+        // In secondary workspaces `.jj/repo` is a file holding the repo path.
+        if repo.is_file() {
+            let target = fs::read_to_string(&repo)
+                .with_context(|| format!("could not read {}", repo.display()))?;
+            let target = PathBuf::from(target.trim());
+            return Ok(match repo.parent() {
+                Some(dir) if target.is_relative() => dir.join(target),
+                _ => target,
+            });
+        }
+        Ok(repo)
+    }
+
+    /// ID of the latest operation, read without snapshotting the working copy.
+    pub fn current_op_id() -> Result<String> {
+        Self::stdout(&[
+            "--ignore-working-copy",
+            "op",
+            "log",
+            "-n",
+            "1",
+            "--no-graph",
+            "-T",
+            "id",
+        ])
+    }
+
+    /// Create a workspace named `name` at `path` whose working copy sits on `rev`.
+    pub fn workspace_add(name: &str, path: &str, rev: &str) -> Result<()> {
+        Self::run(&["workspace", "add", "--name", name, "-r", rev, path])?;
+        Ok(())
+    }
+
+    /// Stop tracking a workspace (its files are left on disk).
+    pub fn workspace_forget(name: &str) -> Result<()> {
+        Self::run(&["workspace", "forget", name])?;
+        Ok(())
+    }
+
+    /// Read a single config value, e.g. `user.name`.
+    pub fn config_get(name: &str) -> Result<String> {
+        Self::stdout(&["config", "get", name])
+    }
+
+    /// Print config settings under `name` as `key = value` lines.
+    pub fn config_list(name: &str) -> Result<String> {
+        Self::stdout(&["config", "list", name])
+    }
+
     /// Create the jjt root bookmark.
     pub fn init_root() -> Result<()> {
         // Check if bookmark exists
@@ -42,20 +121,108 @@ impl Jj {
         Ok(())
     }
 
-    /// Create a new commit as a child of jjt root, return its change ID.
-    pub fn create_child(description: &str) -> Result<String> {
-        let (_, stderr) = Self::run(&["new", "jjt", "--no-edit", "-m", description])?;
-        Self::parse_change_id(&stderr)
+    /// Description of the commit under the `jjt-archive` bookmark, if it exists.
+    pub fn archive_description() -> Result<Option<String>> {
+        let desc = Self::stdout(&[
+            "log",
+            "-r",
+            "present(jjt-archive)",
+            "--no-graph",
+            "-T",
+            "description",
+        ])?;
+        Ok(Some(desc).filter(|d| !d.is_empty()))
     }
 
-    /// Get a commit's description.
-    pub fn get_description(change_id: &str) -> Result<String> {
-        Self::stdout(&["log", "-r", change_id, "--no-graph", "-T", "description"])
+    /// Write the archive commit's description, creating the `jjt-archive`
+    /// bookmark (off root(), so task commits are never rewritten) if needed.
+    pub fn write_archive(description: &str) -> Result<()> {
+        if Self::archive_description()?.is_some() {
+            Self::run(&["describe", "-r", "jjt-archive", "-m", description])?;
+        } else {
+            let (_, stderr) = Self::run(&["new", "root()", "--no-edit", "-m", description])?;
+            let id = Self::parse_change_id(&stderr)?;
+            Self::run(&["bookmark", "create", "jjt-archive", "-r", &id])?;
+        }
+        Ok(())
     }
 
-    /// Update a commit's description.
-    pub fn describe(change_id: &str, description: &str) -> Result<()> {
-        Self::run(&["describe", "-r", change_id, "-m", description])?;
+    /// Task-shaped commits abandoned in recent operations, found through the
+    /// op log ("abandon commit <id>") and read back by commit ID.
+    pub fn abandoned_task_records() -> Result<Vec<TaskRecord>> {
+        let ops = Self::stdout(&[
+            "op",
+            "log",
+            "--no-graph",
+            "-n",
+            "1000",
+            "-T",
+            r#"description ++ "\n""#,
+        ])?;
+        let commits: Vec<&str> = ops
+            .lines()
+            .filter_map(|l| l.strip_prefix("abandon commit "))
+            .filter_map(|rest| rest.split_whitespace().next())
+            .filter(|id| id.chars().all(|c| c.is_ascii_hexdigit()))
+            .collect();
+        if commits.is_empty() {
+            return Ok(Vec::new());
+        }
+        // `present()` skips commits no longer in the repo (e.g. after `jj util gc`),
+        // which would otherwise fail the whole revset.
+        let revset = commits
+            .iter()
+            .map(|id| format!("present({id})"))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let records = Self::records(
+            &["log", "-r", &revset, "--no-graph"],
+            TASK_RECORD_HEADER,
+            "description",
+        )?;
+        Ok(records
+            .into_iter()
+            .filter_map(Self::task_record_from)
+            .collect())
+    }
+
+    /// `--config` overrides that make `who` the author/committer of rewritten commits.
+    fn identity_args(who: Option<&Identity>) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(who) = who {
+            if !who.name.is_empty() {
+                args.push("--config".to_string());
+                args.push(format!("user.name={}", who.name));
+            }
+            if let Some(ref email) = who.email {
+                args.push("--config".to_string());
+                args.push(format!("user.email={email}"));
+            }
+        }
+        args
+    }
+
+    /// Create a new commit as a child of jjt root, authored by `author`,
+    /// return its change ID.
+    pub fn create_child(description: &str, author: Option<&Identity>) -> Result<String> {
+        let identity = Self::identity_args(author);
+        let mut args: Vec<&str> = identity.iter().map(String::as_str).collect();
+        args.extend(["new", "jjt", "--no-edit", "-m", description]);
+        let (_, stderr) = Self::run(&args)?;
+        Self::parse_change_id(&stderr)
+    }
+
+    /// Update a commit's description, committed by `committer`. The author is
+    /// kept, so it keeps recording who created the task.
+    pub fn describe(
+        change_id: &str,
+        description: &str,
+        committer: Option<&Identity>,
+    ) -> Result<()> {
+        let identity = Self::identity_args(committer);
+        let mut args: Vec<&str> = identity.iter().map(String::as_str).collect();
+        args.extend(["describe", "-r", change_id, "-m", description]);
+        Self::run(&args)?;
         Ok(())
     }
 
@@ -65,19 +232,119 @@ impl Jj {
         Ok(())
     }
 
+    /// Current short commit ID of a change, read without snapshotting the working copy.
+    pub fn commit_id(change_id: &str) -> Result<String> {
+        Self::stdout(&[
+            "--ignore-working-copy",
+            "log",
+            "-r",
+            change_id,
+            "--no-graph",
+            "-T",
+            "commit_id.short(12)",
+        ])
+    }
+
     /// Resolve a revision spec (e.g. "@", bookmark name, change ID prefix) to a short change ID.
     pub fn resolve_change(rev: &str) -> Result<String> {
-        Self::stdout(&["log", "-r", rev, "--no-graph", "-T", "change_id.short(12)"])
+        let out = Self::stdout(&[
+            "log",
+            "-r",
+            rev,
+            "--no-graph",
+            "-T",
+            r#"change_id.short(12) ++ "\n""#,
+        ])?;
+        let mut ids = out.lines();
+        match (ids.next(), ids.next()) {
+            (Some(id), None) => Ok(id.to_string()),
+            (None, _) => bail!("{rev:?} resolves to no revision"),
+            _ => bail!("{rev:?} resolves to more than one revision"),
+        }
+    }
+
+    /// Number of commits in a revset.
+    pub fn revset_size(revset: &str) -> Result<usize> {
+        let out = Self::stdout(&[
+            "log",
+            "-r",
+            revset,
+            "--no-graph",
+            "-T",
+            r#"commit_id ++ "\n""#,
+        ])?;
+        Ok(out.lines().count())
+    }
+
+    /// Full change IDs of the commits in a revset.
+    pub fn revset_change_ids(revset: &str) -> Result<Vec<String>> {
+        let out = Self::stdout(&[
+            "log",
+            "-r",
+            revset,
+            "--no-graph",
+            "-T",
+            r#"change_id ++ "\n""#,
+        ])?;
+        Ok(out.lines().map(String::from).collect())
+    }
+
+    /// List all task commits.
+    pub fn list_task_records() -> Result<Vec<TaskRecord>> {
+        // Like `current_op_id`, skip the working-copy snapshot: task commits are
+        // never checked out, and a snapshot would move the operation the task
+        // index is keyed by.
+        let records = Self::records(
+            &[
+                "--ignore-working-copy",
+                "log",
+                "-r",
+                "children(jjt)",
+                "--no-graph",
+            ],
+            TASK_RECORD_HEADER,
+            "description",
+        )?;
+        Ok(records
+            .into_iter()
+            .filter_map(Self::task_record_from)
+            .collect())
+    }
+
+    fn task_record_from((header, description): (String, String)) -> Option<TaskRecord> {
+        let mut fields = header.split('\t');
+        Some(TaskRecord {
+            change_id: fields.next()?.to_string(),
+            author_name: fields.next().unwrap_or_default().to_string(),
+            author_email: fields.next().unwrap_or_default().to_string(),
+            created_at: fields.next().unwrap_or_default().to_string(),
+            updated_at: fields.next().unwrap_or_default().to_string(),
+            commit_id: fields.next().unwrap_or_default().to_string(),
+            description,
+        })
+    }
+
+    /// Previous versions of a task commit as (commit_id timestamp, description)
+    /// pairs, oldest first.
+    pub fn evolog_records(change_id: &str) -> Result<Vec<(String, String)>> {
+        // evolog templates take a CommitEvolutionEntry; the commit is under `commit`.
+        let mut records = Self::records(
+            &["evolog", "-r", change_id, "--no-graph"],
+            r#"commit.commit_id().short(12) ++ " " ++ commit.committer().timestamp().format("%Y-%m-%dT%H:%M:%S%:z")"#,
+            "commit.description()",
+        )?;
+        records.reverse();
+        Ok(records)
     }
 
-    /// List all task commits as (change_id, description) pairs.
-    pub fn list_task_records() -> Result<Vec<(String, String)>> {
+    /// Run a jj command templated to emit one (header, description) block per commit.
+    fn records(args: &[&str], header: &str, description: &str) -> Result<Vec<(String, String)>> {
         let marker = "<<JJT:END>>";
-        let template = format!(
-            r#""<<JJT:" ++ change_id.short(12) ++ ">>\n" ++ description ++ "\n{marker}\n""#
-        );
-        let (stdout, _) =
-            Self::run(&["log", "-r", "children(jjt)", "--no-graph", "-T", &template])?;
+        let template =
+            format!(r#""<<JJT:" ++ {header} ++ ">>\n" ++ {description} ++ "\n{marker}\n""#);
+        let mut full_args = args.to_vec();
+        full_args.extend(["-T", &template]);
+        let (stdout, _) = Self::run(&full_args)?;
 
         let mut results = Vec::new();
         for block in stdout.split(&format!("{marker}\n")) {
@@ -91,14 +358,14 @@ impl Jj {
             let header = &block[..nl];
             let description = &block[nl + 1..];
 
-            let Some(change_id) = header
+            let Some(header) = header
                 .strip_prefix("<<JJT:")
                 .and_then(|s| s.strip_suffix(">>"))
             else {
                 continue;
             };
 
-            results.push((change_id.to_string(), description.to_string()));
+            results.push((header.to_string(), description.to_string()));
         }
         Ok(results)
     }
@@ -0,0 +1,373 @@
+//! Human output for `jjt list`: a table of chosen columns, or a template such
+//! as `{id:12} {priority} {summary}` with one line per task.
+
+use crate::ids::Prefixes;
+use crate::task::{Status, Task};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+
+/// Columns shown when neither `--columns`, `--template` nor config picks any.
+pub const DEFAULT_COLUMNS: &str = "id,status,priority,labels,agent,change,checks,missing,summary";
+
+/// A task plus the board-level facts its row shows.
+pub struct RowView<'a> {
+    pub task: &'a Task,
+    pub is_blocked: bool,
+    pub dangling: &'a [String],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Status,
+    Priority,
+    Estimate,
+    Labels,
+    Agent,
+    Assignees,
+    Change,
+    Checks,
+    Age,
+    Updated,
+    Missing,
+    Summary,
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "id" => Column::Id,
+            "status" => Column::Status,
+            "priority" => Column::Priority,
+            "estimate" => Column::Estimate,
+            "labels" => Column::Labels,
+            "agent" => Column::Agent,
+            "assignees" => Column::Assignees,
+            "change" => Column::Change,
+            "checks" => Column::Checks,
+            "age" => Column::Age,
+            "updated" => Column::Updated,
+            "missing" => Column::Missing,
+            "summary" => Column::Summary,
+            _ => bail!(
+                "unknown column {s:?} (expected id, status, priority, estimate, labels, agent, \
+                 assignees, change, checks, age, updated, missing or summary)"
+            ),
+        })
+    }
+}
+
+impl Column {
+    /// Parse a comma-separated column list.
+    pub fn parse_list(s: &str) -> Result<Vec<Column>> {
+        s.split(',').map(|c| c.trim().parse()).collect()
+    }
+
+    pub fn value(self, row: &RowView, now: DateTime<Utc>) -> String {
+        let t = row.task;
+        match self {
+            Column::Id => t.id.clone(),
+            Column::Status => match t.status {
+                Status::Open if row.is_blocked => "blocked".into(),
+                _ if t.claim_expired(now) => "expired".into(),
+                status => status.to_string(),
+            },
+            Column::Priority => format!("p{}", t.priority),
+            Column::Estimate => t.estimate.map(|e| format!("~{e}")).unwrap_or_default(),
+            Column::Labels => t
+                .labels
+                .iter()
+                .map(|l| format!("#{l}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Column::Agent => t.agent.clone().unwrap_or_default(),
+            Column::Assignees => t.assignees.join(", "),
+            Column::Change => t
+                .change
+                .as_ref()
+                .map(|c| format!("@{c}"))
+                .unwrap_or_default(),
+            Column::Checks => match t.checklist_progress() {
+                (_, 0) => String::new(),
+                (checked, total) => format!("[{checked}/{total}]"),
+            },
+            Column::Age => since(t.created_at.as_deref(), now),
+            Column::Updated => since(t.updated_at.as_deref(), now),
+            Column::Missing => row
+                .dangling
+                .iter()
+                .map(|d| format!("!missing:{d}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Column::Summary => t.summary.clone(),
+        }
+    }
+}
+
+/// Compact time since `ts`: 45m, 5h, 3d, 2w.
+fn since(ts: Option<&str>, now: DateTime<Utc>) -> String {
+    let Some(ts) = ts.and_then(|ts| ts.parse::<DateTime<Utc>>().ok()) else {
+        return String::new();
+    };
+    let minutes = (now - ts).num_minutes().max(0);
+    match minutes {
+        m if m < 60 => format!("{m}m"),
+        m if m < 60 * 24 => format!("{}h", m / 60),
+        m if m < 60 * 24 * 14 => format!("{}d", m / (60 * 24)),
+        m => format!("{}w", m / (60 * 24 * 7)),
+    }
+}
+
+/// One line per row, columns separated by two spaces and padded to their
+/// widest cell. Columns empty in every row are left out; the last is not padded.
+pub fn table(
+    rows: &[&RowView],
+    columns: &[Column],
+    ids: &Prefixes,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|r| columns.iter().map(|c| c.value(r, now)).collect())
+        .collect();
+    let shown: Vec<usize> = (0..columns.len())
+        .filter(|&i| cells.iter().any(|row| !row[i].is_empty()))
+        .collect();
+    let widths: Vec<usize> = shown
+        .iter()
+        .map(|&i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    cells
+        .iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (n, (&i, &width)) in shown.iter().zip(&widths).enumerate() {
+                if n > 0 {
+                    line.push_str("  ");
+                }
+                let width = if n + 1 == shown.len() { 0 } else { width };
+                if columns[i] == Column::Id {
+                    line.push_str(&ids.show(&row[i], width));
+                } else if width == 0 {
+                    line.push_str(&row[i]);
+                } else {
+                    line.push_str(&format!("{:<width$}", row[i]));
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    /// A column, optionally padded or cut to a width.
+    Field(Column, Option<usize>),
+}
+
+/// A line template: literal text with `{column}` or `{column:width}`
+/// placeholders. `{{` and `}}` are literal braces; `\t` and `\n` are escapes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Piece>);
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '\\' if matches!(chars.peek(), Some('n' | 't')) => {
+                    text.push(if chars.next() == Some('n') {
+                        '\n'
+                    } else {
+                        '\t'
+                    });
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => bail!("unterminated '{{' in template"),
+                            Some(c) => spec.push(c),
+                        }
+                    }
+                    let (name, width) = match spec.split_once(':') {
+                        Some((name, width)) => (
+                            name,
+                            Some(width.parse().with_context(|| {
+                                format!("invalid width in template field {{{spec}}}")
+                            })?),
+                        ),
+                        None => (spec.as_str(), None),
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(name.trim().parse()?, width));
+                }
+                '}' => bail!("unmatched '}}' in template (use '}}}}' for a literal brace)"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Template(pieces))
+    }
+}
+
+impl Template {
+    pub fn render(&self, row: &RowView, ids: &Prefixes, now: DateTime<Utc>) -> String {
+        let mut out = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Field(column, width) => {
+                    let mut value = column.value(row, now);
+                    if let Some(width) = width {
+                        value = truncate(&value, *width);
+                    }
+                    let width = width.unwrap_or(0);
+                    if *column == Column::Id {
+                        out.push_str(&ids.show(&value, width));
+                    } else {
+                        out.push_str(&format!("{value:<width$}"));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Cut `line` to `width` visible characters, marking the cut with "…".
+/// ANSI color sequences are kept and do not count towards the width.
+pub fn truncate(line: &str, width: usize) -> String {
+    if visible_len(line) <= width {
+        return line.to_string();
+    }
+    let mut out = String::new();
+    let mut shown = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            out.extend(chars.by_ref().take_while(|&c| c != 'm'));
+            out.push('m');
+        } else if shown + 1 < width {
+            out.push(c);
+            shown += 1;
+        } else {
+            break;
+        }
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    if out.contains('\x1b') {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+fn visible_len(line: &str) -> usize {
+    let mut len = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().take_while(|&c| c != 'm').for_each(drop);
+        } else {
+            len += 1;
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, headers: &str) -> Task {
+        let desc = format!("jjt: Summary of {id}\n{headers}");
+        Task::from_description(id.into(), &desc).unwrap()
+    }
+
+    #[test]
+    fn table_pads_and_drops_empty_columns() {
+        let a = task("aaaa", "status: open\npriority: 1\nlabels: api ui\n");
+        let b = task("bb", "status: claimed\npriority: 3\nagent: claude\n");
+        let a = RowView {
+            task: &a,
+            is_blocked: true,
+            dangling: &[],
+        };
+        let b = RowView {
+            task: &b,
+            is_blocked: false,
+            dangling: &[],
+        };
+        let columns = Column::parse_list(DEFAULT_COLUMNS).unwrap();
+        let ids = Prefixes::new(["aaaa", "bb"], false);
+        let lines = table(&[&a, &b], &columns, &ids, Utc::now());
+        assert_eq!(
+            lines,
+            vec![
+                "aaaa  blocked  p1  #api #ui          Summary of aaaa",
+                "bb    claimed  p3            claude  Summary of bb",
+            ]
+        );
+        assert!(Column::parse_list("id,colour").is_err());
+    }
+
+    #[test]
+    fn templates() {
+        let t = task("abc", "status: open\npriority: 2\nestimate: 5\n");
+        let row = RowView {
+            task: &t,
+            is_blocked: false,
+            dangling: &[],
+        };
+        let now = Utc::now();
+        let tmpl: Template = "{id:5}|{priority} {{{estimate}}}\\t{summary:8}"
+            .parse()
+            .unwrap();
+        let ids = Prefixes::new(["abc"], false);
+        assert_eq!(tmpl.render(&row, &ids, now), "abc  |p2 {~5}\tSummary…");
+        assert!("{nope}".parse::<Template>().is_err());
+        assert!("{id:x}".parse::<Template>().is_err());
+        assert!("oops}".parse::<Template>().is_err());
+        let err = "{id {summary}".parse::<Template>().unwrap_err();
+        assert_eq!(err.to_string(), "unterminated '{' in template");
+        assert!("{id".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn truncates_to_width() {
+        assert_eq!(truncate("hello world", 6), "hello…");
+        assert_eq!(truncate("hello", 6), "hello");
+        let colored = "\x1b[1;35mab\x1b[0mcdef";
+        assert_eq!(truncate(colored, 6), colored);
+        assert_eq!(truncate(colored, 3), "\x1b[1;35mab\x1b[0m…\x1b[0m");
+    }
+}
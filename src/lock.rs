@@ -0,0 +1,61 @@
+use crate::jj::Jj;
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long to wait for another jjt process before giving up.
+const WAIT: Duration = Duration::from_secs(10);
+
+/// Whether this process holds the lock, so nested acquires (a command holding
+/// it across select-and-claim, then saving) do not wait on themselves.
+static HELD: AtomicBool = AtomicBool::new(false);
+
+/// Exclusive lock serialising task mutations across jjt processes on one repo.
+/// An OS advisory lock on `.jj/repo/jjt.lock`, released when the file is
+/// closed on drop or when the process dies. The file itself is never removed.
+pub struct RepoLock {
+    /// `None` for a nested acquire, which leaves release to the outer guard.
+    file: Option<File>,
+}
+
+impl RepoLock {
+    pub fn acquire() -> Result<RepoLock> {
+        if HELD.load(Ordering::SeqCst) {
+            return Ok(RepoLock { file: None });
+        }
+        let path = Jj::repo_dir()?.join("jjt.lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("could not open {}", path.display()))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    HELD.store(true, Ordering::SeqCst);
+                    return Ok(RepoLock { file: Some(file) });
+                }
+                Err(TryLockError::WouldBlock) => {
+                    if start.elapsed() > WAIT {
+                        bail!("timed out waiting for {}", path.display());
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("could not lock {}", path.display()))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            HELD.store(false, Ordering::SeqCst);
+        }
+    }
+}
@@ -1,13 +1,35 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use chrono::Utc;
-use clap::{Parser, Subcommand};
-use std::collections::HashSet;
-
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+
+mod config;
+mod export;
+mod graph;
+mod identity;
+mod ids;
+mod index;
 mod jj;
+mod layout;
+mod lock;
+mod query;
+mod search;
 mod task;
 
-use jj::Jj;
-use task::{Link, LinkKind, Note, Status, Task};
+use config::Config;
+use export::Board;
+use graph::DepGraph;
+use identity::Identity;
+use ids::{Match, Prefixes};
+use jj::{Jj, TaskRecord};
+use layout::{Column, RowView, Template};
+use lock::RepoLock;
+use query::{Expr, Scope};
+use task::{
+    Archive, Backlinks, CheckItem, Link, LinkEffect, LinkKind, Note, NoteKind, RevBlocker,
+    RevCondition, Status, Task,
+};
 
 #[derive(Parser)]
 #[command(name = "jjt", about = "jj-native task tracker")]
@@ -34,49 +56,233 @@ enum Command {
         #[arg(short, long, default_value_t = 2)]
         priority: u8,
 
+        /// Effort estimate (any unit, used to weight the critical path)
+        #[arg(short, long)]
+        estimate: Option<u32>,
+
         /// Link to a jj change (use @ for current change)
         #[arg(short, long)]
         change: Option<String>,
-    },
 
-    /// List tasks
-    List {
-        /// Only ready tasks (open, no active blockers)
+        /// Shell command that must succeed before the task can be closed
         #[arg(long)]
-        ready: bool,
+        verify: Option<String>,
+
+        /// Label (repeatable)
+        #[arg(short, long = "label")]
+        labels: Vec<String>,
+    },
+
+    /// Set or clear a task's effort estimate
+    Estimate {
+        id: String,
+
+        #[arg(required_unless_present = "clear")]
+        estimate: Option<u32>,
+
+        /// Remove the estimate
+        #[arg(long, conflicts_with = "estimate")]
+        clear: bool,
+    },
+
+    /// Add or remove labels
+    Label {
+        id: String,
+
+        #[arg(required = true)]
+        labels: Vec<String>,
 
-        /// Only blocked tasks
+        /// Remove instead of add
         #[arg(long)]
-        blocked: bool,
+        remove: bool,
+    },
 
-        /// Only tasks claimed by you
+    /// Show the highest-priority ready task, optionally claiming it atomically
+    ///
+    /// Exits with status 3 when no task is ready.
+    Next {
+        /// Claim the selected task (serialised against other jjt processes)
         #[arg(long)]
-        mine: bool,
+        claim: bool,
+
+        /// Only tasks with this label (repeatable; all must match)
+        #[arg(short, long = "label")]
+        labels: Vec<String>,
+
+        /// Only tasks with priority at or above this (e.g. 2 for p1 and p2)
+        #[arg(long, value_name = "N")]
+        max_priority: Option<u8>,
+
+        /// Agent name (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        agent: Option<String>,
+
+        /// Lease length for the claim (defaults to jjt.claim.lease)
+        #[arg(long, value_name = "AGE")]
+        lease: Option<String>,
+    },
+
+    /// List tasks
+    List {
+        #[command(flatten)]
+        filter: ListFilter,
+
+        #[command(flatten)]
+        format: ListFormat,
+    },
+
+    /// List every task (done included) linked to a revision, e.g. `jjt for-rev @`
+    ForRev {
+        /// Revision or revset
+        rev: String,
+    },
+
+    /// Search summaries, notes and note authors
+    Search {
+        #[arg(required = true)]
+        terms: Vec<String>,
 
-        /// Include done tasks
+        /// Only tasks with this status
         #[arg(long)]
-        done: bool,
+        status: Option<Status>,
 
-        /// Show all tasks
+        /// Only tasks with this label (repeatable; all must match)
+        #[arg(short, long = "label")]
+        labels: Vec<String>,
+    },
+
+    /// Export tasks and their dependencies as a DOT or Mermaid graph
+    Graph {
+        #[command(flatten)]
+        filter: ListFilter,
+
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Also draw each task's linked jj change as a node
         #[arg(long)]
-        all: bool,
+        changes: bool,
     },
 
     /// Show task details
     Show {
         /// Change ID (or prefix)
         id: String,
+
+        /// Only show notes of this kind
+        #[arg(long, value_name = "KIND")]
+        notes: Option<NoteKind>,
+    },
+
+    /// Add or remove assignees
+    Assign {
+        id: String,
+
+        /// Agents to assign
+        #[arg(required = true)]
+        agents: Vec<String>,
+
+        /// Remove instead of add
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Request review from one or more agents
+    ReviewRequest {
+        id: String,
+
+        /// Reviewers to request
+        #[arg(required = true)]
+        agents: Vec<String>,
+
+        /// Remove instead of add
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Watch a task (or stop watching with --remove)
+    Watch {
+        id: String,
+
+        /// Watcher (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        agent: Option<String>,
+
+        /// Stop watching
+        #[arg(long)]
+        remove: bool,
     },
 
     /// Claim a task
     Claim {
         id: String,
 
-        /// Agent name (defaults to $JJT_AGENT or $USER)
+        /// Agent name (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        agent: Option<String>,
+
+        /// Lease length (e.g. 30m, 2h); defaults to jjt.claim.lease, else no expiry
+        #[arg(long, value_name = "AGE")]
+        lease: Option<String>,
+
+        /// Take over a task claimed by someone else (recorded in a handoff note)
+        #[arg(long)]
+        steal: bool,
+    },
+
+    /// Give up your claim, returning the task to open
+    Release {
+        id: String,
+
+        /// Agent holding the claim (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        agent: Option<String>,
+
+        /// Context for whoever picks it up next
+        #[arg(short, long)]
+        note: Option<String>,
+    },
+
+    /// Hand a task over to another agent
+    Handoff {
+        id: String,
+
+        /// Agent holding the claim (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        agent: Option<String>,
+
+        /// Agent receiving the task
+        #[arg(long)]
+        to: String,
+
+        /// Context for the receiving agent
+        #[arg(short, long)]
+        note: String,
+
+        /// Lease length for the new claim (defaults to jjt.claim.lease)
+        #[arg(long, value_name = "AGE")]
+        lease: Option<String>,
+    },
+
+    /// Extend the lease on a task you have claimed
+    Heartbeat {
+        id: String,
+
+        /// Agent holding the claim (defaults to $JJT_AGENT or jj's user.name/user.email)
         #[arg(long, env = "JJT_AGENT")]
         agent: Option<String>,
+
+        /// New lease length from now (defaults to jjt.claim.lease)
+        #[arg(long, value_name = "AGE")]
+        lease: Option<String>,
     },
 
+    /// Reset claims whose lease has expired back to open
+    Reap,
+
+    /// Show claimed-task load per agent against WIP limits
+    Wip,
+
     /// Mark a task as done
     Done {
         id: String,
@@ -84,6 +290,22 @@ enum Command {
         /// Optional closing note
         #[arg(short, long)]
         note: Option<String>,
+
+        /// Close even if checklist items are unchecked
+        #[arg(long)]
+        force: bool,
+
+        /// Close without running the verify command (recorded in a note)
+        #[arg(long)]
+        skip_verify: bool,
+    },
+
+    /// Set a task's verify command, or run it without closing the task
+    Verify {
+        id: String,
+
+        /// Command to set (empty string clears it); omit to run the current one
+        command: Option<String>,
     },
 
     /// Reopen a task
@@ -95,8 +317,38 @@ enum Command {
         id: String,
 
         /// Task that blocks it (change ID)
+        #[arg(long, required_unless_present = "on_rev", conflicts_with = "on_rev")]
+        on: Option<String>,
+
+        /// Revset that blocks it until --until holds (e.g. a change ID)
+        #[arg(long, value_name = "REVSET")]
+        on_rev: Option<String>,
+
+        /// With --on-rev: landed (in ::trunk()), conflict_free, or exists
+        #[arg(long, requires = "on_rev", default_value = "landed")]
+        until: RevCondition,
+    },
+
+    /// Add a soft dependency: schedule a task after another without blocking it
+    After {
+        id: String,
+
+        /// Task it should come after (change ID)
         #[arg(long)]
         on: String,
+
+        /// Remove instead of add
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Show everything a task waits on and everything waiting on it
+    Deps { id: String },
+
+    /// Show the longest chain of unfinished blockers, weighted by estimate
+    CriticalPath {
+        /// Task the chain leads to (default: the longest on the board)
+        id: Option<String>,
     },
 
     /// Remove a blocking dependency
@@ -104,20 +356,66 @@ enum Command {
         id: String,
 
         /// Blocker to remove (change ID)
-        #[arg(long)]
-        from: String,
+        #[arg(
+            long,
+            required_unless_present = "from_rev",
+            conflicts_with = "from_rev"
+        )]
+        from: Option<String>,
+
+        /// Revset blocker to remove, as given to `block --on-rev`
+        #[arg(long, value_name = "REVSET")]
+        from_rev: Option<String>,
     },
 
-    /// Add a note
+    /// Add a note, or edit, remove or reply to one
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Note {
-        id: String,
+        #[command(subcommand)]
+        action: Option<NoteAction>,
+
+        #[arg(required = true)]
+        id: Option<String>,
 
         /// Note body
-        body: String,
+        #[arg(required = true)]
+        body: Option<String>,
 
-        /// Author (defaults to $JJT_AGENT or $USER)
+        /// Author (defaults to $JJT_AGENT or jj's user.name/user.email)
         #[arg(long, env = "JJT_AGENT")]
         author: Option<String>,
+
+        /// Note kind: decision, question, answer, blocker, handoff or log
+        #[arg(long)]
+        kind: Option<NoteKind>,
+    },
+
+    /// List unanswered questions across all tasks
+    Questions,
+
+    /// Show a task's history, including edited and deleted notes
+    Log { id: String },
+
+    /// Check off an acceptance criterion, or manage the checklist
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Check {
+        #[command(subcommand)]
+        action: Option<CheckAction>,
+
+        #[arg(required = true)]
+        id: Option<String>,
+
+        /// Item number (1-based)
+        #[arg(required = true)]
+        item: Option<usize>,
+    },
+
+    /// Uncheck an acceptance criterion
+    Uncheck {
+        id: String,
+
+        /// Item number (1-based)
+        item: usize,
     },
 
     /// Link two tasks
@@ -132,6 +430,34 @@ enum Command {
 
         #[arg(long, group = "link_kind")]
         duplicates: Option<String>,
+
+        /// With --duplicates: close this task, copying its notes and moving
+        /// its blockers onto the canonical one
+        #[arg(long, requires = "duplicates")]
+        close: bool,
+    },
+
+    /// Remove links from a task, undoing what a duplicates/supersedes link changed
+    Unlink {
+        id: String,
+
+        /// Linked task
+        target: String,
+
+        /// Only remove links of this kind: relates_to, duplicates or supersedes
+        #[arg(long)]
+        kind: Option<LinkKind>,
+    },
+
+    /// Check the board for problems
+    Doctor {
+        /// Only look for blocker cycles
+        #[arg(long)]
+        cycles: bool,
+
+        /// Only look for blockers that no longer exist and were not decayed
+        #[arg(long)]
+        dangling: bool,
     },
 
     /// Abandon old done tasks
@@ -139,38 +465,439 @@ enum Command {
         /// Age threshold in days (e.g. 7d, 30d)
         #[arg(long, default_value = "7d")]
         before: String,
+
+        /// Also drop the decayed tasks from their dependents' blocked_by
+        #[arg(long)]
+        prune: bool,
+    },
+}
+
+#[derive(Args, Default)]
+struct ListFilter {
+    /// Filter expression, e.g. 'label:api & priority<=2 & !blocked', or @name for
+    /// the query saved as jjt.queries.<name>. The flags below are shorthands.
+    query: Option<String>,
+
+    /// Only ready tasks (open, no active blockers)
+    #[arg(long)]
+    ready: bool,
+
+    /// Only blocked tasks
+    #[arg(long)]
+    blocked: bool,
+
+    /// Only tasks claimed by or assigned to you
+    #[arg(long)]
+    mine: bool,
+
+    /// Include done tasks
+    #[arg(long)]
+    done: bool,
+
+    /// Show all tasks
+    #[arg(long)]
+    all: bool,
+
+    /// Only open tasks awaiting review by this agent ("me" for yourself)
+    #[arg(long, value_name = "AGENT")]
+    reviewer: Option<String>,
+
+    /// Only open tasks you are watching
+    #[arg(long)]
+    watching: bool,
+
+    /// Only tasks changed within this age (e.g. 30m, 12h, 1d, 2w)
+    #[arg(long, value_name = "AGE")]
+    updated_since: Option<String>,
+
+    /// Only tasks whose linked change is in this jj revset (e.g. '::@', 'trunk()..@')
+    #[arg(long, value_name = "REVSET")]
+    change_in: Option<String>,
+}
+
+impl ListFilter {
+    /// The query these flags stand for: each set flag adds a term, and with no
+    /// query and no status flag, done tasks are left out.
+    fn to_query(&self) -> String {
+        let mut terms: Vec<String> = Vec::new();
+        if let Some(ref query) = self.query {
+            terms.push(format!("({query})"));
+        }
+        let open = "!status:done".to_string();
+        if self.all {
+            terms.push("all".into());
+        }
+        if self.ready {
+            terms.push("ready".into());
+        }
+        if self.blocked {
+            terms.push("status:open & blocked".into());
+        }
+        if self.mine {
+            terms.extend([open.clone(), "mine".into()]);
+        }
+        if let Some(ref reviewer) = self.reviewer {
+            terms.extend([open.clone(), format!("reviewer:{}", query::quote(reviewer))]);
+        }
+        if self.watching {
+            terms.extend([open.clone(), "watcher:me".into()]);
+        }
+        if self.done {
+            terms.push("status:done".into());
+        }
+        if terms.is_empty() {
+            terms.push(open);
+        }
+        if let Some(ref age) = self.updated_since {
+            terms.push(format!("updated:{}", query::quote(age)));
+        }
+        terms.join(" & ")
+    }
+
+    /// The tasks this filter selects, in board order.
+    fn select<'a>(&self, tasks: &'a [Task], blocked: &HashSet<String>) -> Result<Vec<&'a Task>> {
+        let config = Config::load();
+        let saved = |name: &str| config.get(&format!("jjt.queries.{name}")).map(String::from);
+        let expr = Expr::parse(&self.to_query(), &saved)?;
+        let scope = Scope {
+            now: Utc::now(),
+            me: current_identity(),
+            blocked,
+        };
+        let changes = match self.change_in {
+            Some(ref revset) => Some(Jj::revset_change_ids(revset)?),
+            None => None,
+        };
+        Ok(tasks
+            .iter()
+            .filter(|t| expr.matches(t, &scope))
+            .filter(|t| match changes {
+                // Tasks store a short change ID; the revset yields full ones.
+                Some(ref ids) => t
+                    .change
+                    .as_ref()
+                    .is_some_and(|c| ids.iter().any(|id| id.starts_with(c.as_str()))),
+                None => true,
+            })
+            .collect())
+    }
+}
+
+/// How `list` prints rows. Unset options fall back to `jjt.list.*` config.
+#[derive(Args, Default)]
+struct ListFormat {
+    /// Sort order (default: jj log order, or priority with --ready)
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+
+    /// Reverse the sort order
+    #[arg(long, overrides_with = "no_reverse")]
+    reverse: bool,
+
+    /// Do not reverse the sort order, even if `jjt.list.reverse` is set
+    #[arg(long, overrides_with = "reverse")]
+    no_reverse: bool,
+
+    /// Show at most this many tasks
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Comma-separated columns: id, status, priority, estimate, labels, agent,
+    /// assignees, change, checks, age, updated, missing, summary
+    #[arg(long, value_name = "LIST")]
+    columns: Option<String>,
+
+    /// One line per task, e.g. '{id:12} {priority} {summary}' ({{ and }} for braces)
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "columns")]
+    template: Option<String>,
+}
+
+impl ListFormat {
+    /// Fill unset options from `jjt.list.sort`, `.reverse`, `.limit`,
+    /// `.columns` and `.template`.
+    fn with_defaults(mut self, config: &Config) -> Result<ListFormat> {
+        if self.sort.is_none() {
+            if let Some(sort) = config.get("jjt.list.sort") {
+                self.sort = Some(
+                    SortKey::from_str(sort, true)
+                        .map_err(|e| anyhow::anyhow!("jjt.list.sort: {e}"))?,
+                );
+            }
+        }
+        if !self.reverse && !self.no_reverse {
+            self.reverse = config.get_bool("jjt.list.reverse", false);
+        }
+        if self.limit.is_none() {
+            if let Some(limit) = config.get("jjt.list.limit") {
+                self.limit = Some(limit.parse().map_err(|_| {
+                    anyhow::anyhow!("jjt.list.limit: invalid limit {limit:?}, expected a number")
+                })?);
+            }
+        }
+        // An explicit --columns overrides a configured template and vice versa.
+        if self.columns.is_none() && self.template.is_none() {
+            self.template = config.get("jjt.list.template").map(String::from);
+            if self.template.is_none() {
+                self.columns = config.get("jjt.list.columns").map(String::from);
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    /// By task ID
+    Id,
+    /// Open, then claimed, then done
+    Status,
+    /// Oldest first
+    Created,
+    /// Least recently updated first
+    Updated,
+    /// Highest priority first, each task after its `after` hints
+    Priority,
+    /// Tasks that unblock the most other unfinished tasks first
+    Unblocks,
+}
+
+#[derive(Subcommand)]
+enum CheckAction {
+    /// Add a checklist item
+    Add {
+        id: String,
+
+        /// Item text
+        text: String,
+    },
+
+    /// Remove a checklist item
+    Rm {
+        id: String,
+
+        /// Item number (1-based)
+        item: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum NoteAction {
+    /// Replace a note's body
+    Edit {
+        id: String,
+
+        /// Note ID (as shown by `jjt show`)
+        note: u32,
+
+        /// New body
+        body: String,
+    },
+
+    /// Delete a note, leaving a tombstone
+    Rm {
+        id: String,
+
+        /// Note ID
+        note: u32,
+    },
+
+    /// Reply to a note
+    Reply {
+        id: String,
+
+        /// Note ID to reply to
+        note: u32,
+
+        /// Reply body
+        body: String,
+
+        /// Author (defaults to $JJT_AGENT or jj's user.name/user.email)
+        #[arg(long, env = "JJT_AGENT")]
+        author: Option<String>,
+
+        /// Note kind: decision, question, answer, blocker, handoff or log
+        #[arg(long)]
+        kind: Option<NoteKind>,
     },
 }
 
+/// A command that finished without an error but must exit with this status
+/// (e.g. `next` with nothing ready). `main` exits once every lock is dropped.
+#[derive(Debug)]
+struct ExitStatus(i32);
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitStatus {}
+
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    match run(Cli::parse()) {
+        Err(e) => match e.downcast_ref::<ExitStatus>() {
+            Some(ExitStatus(code)) => std::process::exit(*code),
+            None => Err(e),
+        },
+        ok => ok,
+    }
+}
 
+fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Command::Init => cmd_init(cli.json),
         Command::New {
             summary,
             priority,
+            estimate,
             change,
-        } => cmd_new(summary, priority, change, cli.json),
-        Command::List {
-            ready,
-            blocked,
-            mine,
-            done,
-            all,
-        } => cmd_list(ready, blocked, mine, done, all, cli.json),
-        Command::Show { id } => cmd_show(&id, cli.json),
-        Command::Claim { id, agent } => cmd_claim(&id, agent, cli.json),
-        Command::Done { id, note } => cmd_done(&id, note, cli.json),
+            verify,
+            labels,
+        } => cmd_new(
+            summary, priority, estimate, change, verify, labels, cli.json,
+        ),
+        Command::Estimate {
+            id,
+            estimate,
+            clear: _,
+        } => cmd_estimate(&id, estimate, cli.json),
+        Command::Label { id, labels, remove } => cmd_label(&id, labels, remove, cli.json),
+        Command::Next {
+            claim,
+            labels,
+            max_priority,
+            agent,
+            lease,
+        } => cmd_next(claim, &labels, max_priority, agent, lease, cli.json),
+        Command::List { filter, format } => {
+            let format = format.with_defaults(&Config::load())?;
+            cmd_list(&filter, format, cli.json)
+        }
+        Command::Assign { id, agents, remove } => {
+            cmd_people(&id, Role::Assignee, agents, remove, cli.json)
+        }
+        Command::ReviewRequest { id, agents, remove } => {
+            cmd_people(&id, Role::Reviewer, agents, remove, cli.json)
+        }
+        Command::Watch { id, agent, remove } => {
+            let agent = agent
+                .or_else(default_agent)
+                .unwrap_or_else(|| "unknown".into());
+            cmd_people(&id, Role::Watcher, vec![agent], remove, cli.json)
+        }
+        Command::ForRev { rev } => {
+            let filter = ListFilter {
+                all: true,
+                change_in: Some(rev),
+                ..Default::default()
+            };
+            // Configured columns apply, but never a limit: every linked task is shown.
+            let format = ListFormat::default().with_defaults(&Config::load())?;
+            let format = ListFormat {
+                limit: None,
+                ..format
+            };
+            cmd_list(&filter, format, cli.json)
+        }
+        Command::Search {
+            terms,
+            status,
+            labels,
+        } => cmd_search(&terms, status, &labels, cli.json),
+        Command::Graph {
+            filter,
+            format,
+            changes,
+        } => cmd_graph(&filter, format, changes),
+        Command::Show { id, notes } => cmd_show(&id, notes, cli.json),
+        Command::Claim {
+            id,
+            agent,
+            lease,
+            steal,
+        } => cmd_claim(&id, agent, lease, steal, cli.json),
+        Command::Release { id, agent, note } => cmd_release(&id, agent, note, cli.json),
+        Command::Handoff {
+            id,
+            agent,
+            to,
+            note,
+            lease,
+        } => cmd_handoff(&id, agent, &to, &note, lease, cli.json),
+        Command::Heartbeat { id, agent, lease } => cmd_heartbeat(&id, agent, lease, cli.json),
+        Command::Reap => cmd_reap(cli.json),
+        Command::Wip => cmd_wip(cli.json),
+        Command::Done {
+            id,
+            note,
+            force,
+            skip_verify,
+        } => cmd_done(&id, note, force, skip_verify, cli.json),
+        Command::Verify { id, command } => cmd_verify(&id, command, cli.json),
         Command::Reopen { id } => cmd_reopen(&id, cli.json),
-        Command::Block { id, on } => cmd_block(&id, &on, cli.json),
-        Command::Unblock { id, from } => cmd_unblock(&id, &from, cli.json),
-        Command::Note { id, body, author } => cmd_note(&id, &body, author, cli.json),
+        Command::Block {
+            id,
+            on,
+            on_rev,
+            until,
+        } => match (on, on_rev) {
+            (_, Some(revset)) => cmd_block_rev(&id, revset, until, cli.json),
+            (Some(on), None) => cmd_block(&id, &on, cli.json),
+            (None, None) => bail!("specify --on or --on-rev"),
+        },
+        Command::After { id, on, remove } => cmd_after(&id, &on, remove, cli.json),
+        Command::Deps { id } => cmd_deps(&id, cli.json),
+        Command::CriticalPath { id } => cmd_critical_path(id.as_deref(), cli.json),
+        Command::Unblock { id, from, from_rev } => match (from, from_rev) {
+            (_, Some(revset)) => cmd_unblock_rev(&id, &revset, cli.json),
+            (Some(from), None) => cmd_unblock(&id, &from, cli.json),
+            (None, None) => bail!("specify --from or --from-rev"),
+        },
+        Command::Note {
+            action,
+            id,
+            body,
+            author,
+            kind,
+        } => match action {
+            Some(NoteAction::Edit { id, note, body }) => cmd_note_edit(&id, note, body, cli.json),
+            Some(NoteAction::Rm { id, note }) => cmd_note_rm(&id, note, cli.json),
+            Some(NoteAction::Reply {
+                id,
+                note,
+                body,
+                author,
+                kind,
+            }) => cmd_note(&id, &body, author, kind, Some(note), cli.json),
+            None => match (id, body) {
+                (Some(id), Some(body)) => cmd_note(&id, &body, author, kind, None, cli.json),
+                _ => bail!("usage: jjt note <id> <body>"),
+            },
+        },
+        Command::Questions => cmd_questions(cli.json),
+        Command::Log { id } => cmd_log(&id, cli.json),
+        Command::Check { action, id, item } => match action {
+            Some(CheckAction::Add { id, text }) => cmd_check_add(&id, text, cli.json),
+            Some(CheckAction::Rm { id, item }) => cmd_check_rm(&id, item, cli.json),
+            None => match (id, item) {
+                (Some(id), Some(item)) => cmd_check(&id, item, true, cli.json),
+                _ => bail!("usage: jjt check <id> <item>"),
+            },
+        },
+        Command::Uncheck { id, item } => cmd_check(&id, item, false, cli.json),
         Command::Link {
             id,
             relates_to,
             supersedes,
             duplicates,
+            close,
         } => {
             let (target, kind) = if let Some(t) = relates_to {
                 (t, LinkKind::RelatesTo)
@@ -181,46 +908,401 @@ fn main() -> Result<()> {
             } else {
                 bail!("specify --relates-to, --supersedes, or --duplicates");
             };
-            cmd_link(&id, &target, kind, cli.json)
+            cmd_link(&id, &target, kind, close, cli.json)
         }
-        Command::Decay { before } => cmd_decay(&before, cli.json),
+        Command::Unlink { id, target, kind } => cmd_unlink(&id, &target, kind, cli.json),
+        Command::Doctor { cycles, dangling } => cmd_doctor(cycles, dangling, cli.json),
+        Command::Decay { before, prune } => cmd_decay(&before, prune, cli.json),
     }
 }
 
 // --- Helpers ---
 
+/// The acting agent: `$JJT_AGENT` if set, else jj's `user.name` and `user.email`.
+fn current_identity() -> Option<Identity> {
+    if let Ok(agent) = std::env::var("JJT_AGENT") {
+        if !agent.trim().is_empty() {
+            return Some(Identity::parse(&agent));
+        }
+    }
+    let name = Jj::config_get("user.name").ok().filter(|s| !s.is_empty());
+    let email = Jj::config_get("user.email").ok().filter(|s| !s.is_empty());
+    if name.is_none() && email.is_none() {
+        return None;
+    }
+    Some(Identity {
+        name: name.unwrap_or_default(),
+        email,
+    })
+}
+
 fn default_agent() -> Option<String> {
-    std::env::var("JJT_AGENT")
-        .ok()
-        .or_else(|| std::env::var("USER").ok())
+    current_identity()
+        .map(|id| id.to_string())
+        .filter(|a| !a.trim().is_empty())
 }
 
-/// Load a task by change ID. Resolves to canonical form first.
-fn load_task(change_id: &str) -> Result<Task> {
-    let canonical = Jj::resolve_change(change_id)?;
-    let desc = Jj::get_description(&canonical)?;
-    Task::from_description(canonical, &desc)
+/// `agent` if given, else the current agent. Blank names are refused: notes
+/// and claims record them in header lines.
+fn resolve_agent(agent: Option<String>) -> Result<String> {
+    match agent {
+        Some(agent) => {
+            check_agent(&agent)?;
+            Ok(agent)
+        }
+        None => Ok(default_agent().unwrap_or_else(|| "unknown".into())),
+    }
 }
 
-/// Save a task back to jj by updating its commit description.
-fn save_task(task: &Task) -> Result<()> {
-    Jj::describe(&task.id, &task.to_description())
+fn check_agent(agent: &str) -> Result<()> {
+    if agent.trim().is_empty() {
+        bail!("agent name must not be empty");
+    }
+    Ok(())
 }
 
-/// Resolve a change spec (could be @, a prefix, a full ID) to a change ID.
-fn resolve_change(spec: &str) -> Result<String> {
+/// Load a task by ID; see `find_task`.
+fn load_task(spec: &str) -> Result<Task> {
+    let tasks = load_all_tasks()?;
+    find_task(spec, &tasks).cloned()
+}
+
+/// Resolve a task ID among task commits only: a unique prefix of a task's
+/// ID, else any jj revision (e.g. `@-` or a full change ID) that is a task.
+fn find_task<'a>(spec: &str, tasks: &'a [Task]) -> Result<&'a Task> {
+    let id = match ids::match_prefix(spec, tasks.iter().map(|t| t.id.as_str())) {
+        Match::One(id) => id.to_string(),
+        Match::Ambiguous(ids) => {
+            bail!(
+                "task ID prefix {spec:?} is ambiguous: it matches {}",
+                ids.join(", ")
+            )
+        }
+        Match::None => match Jj::resolve_change(spec) {
+            Ok(id) => id,
+            Err(_) => bail!("no task matches {spec:?}"),
+        },
+    };
+    tasks
+        .iter()
+        .find(|t| t.id == id)
+        .with_context(|| format!("{spec:?} resolves to change {id}, which is not a task"))
+}
+
+/// Resolve `spec` as a task ID, else as one of `refs` verbatim: IDs a task
+/// still names (blockers, links) after their task was decayed or abandoned,
+/// so they can still be removed.
+fn resolve_ref<'a>(
+    spec: &str,
+    tasks: &[Task],
+    mut refs: impl Iterator<Item = &'a String>,
+) -> Result<String> {
+    match find_task(spec, tasks) {
+        Ok(t) => Ok(t.id.clone()),
+        Err(_) if refs.any(|r| r == spec) => Ok(spec.to_string()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Shortest unique prefixes of the board's task IDs, highlighted on a terminal.
+fn id_prefixes(tasks: &[Task]) -> Prefixes {
+    Prefixes::new(
+        tasks.iter().map(|t| t.id.as_str()),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+/// Parse a task commit, filling in fields derived from commit metadata.
+fn task_from_record(record: TaskRecord) -> Result<Task> {
+    let mut task = Task::from_description(record.change_id, &record.description)?;
+    let creator = Identity {
+        name: record.author_name,
+        email: Some(record.author_email).filter(|e| !e.is_empty()),
+    };
+    task.created_by = Some(creator.to_string()).filter(|c| !c.is_empty());
+    task.created_at = Some(record.created_at).filter(|t| !t.is_empty());
+    task.updated_at = Some(record.updated_at).filter(|t| !t.is_empty());
+    task.commit_id = Some(record.commit_id).filter(|c| !c.is_empty());
+    Ok(task)
+}
+
+/// Save a task back to jj by updating its commit description, committed as
+/// the current agent.
+fn save_task(task: &Task) -> Result<()> {
+    write_task(task, current_identity().as_ref())
+}
+
+/// Save a task, committed as `actor` (e.g. an explicit `--agent`).
+fn save_task_as(task: &Task, actor: &str) -> Result<()> {
+    write_task(task, Some(&Identity::parse(actor)))
+}
+
+/// Every task write goes through here: under the repo lock, and only if the
+/// task commit is still the one it was read from, so a concurrent jjt (or jj)
+/// change is never overwritten with an older description.
+fn write_task(task: &Task, committer: Option<&Identity>) -> Result<()> {
+    let _lock = RepoLock::acquire()?;
+    if let Some(ref read) = task.commit_id {
+        let current = Jj::commit_id(&task.id)?;
+        if current != *read {
+            bail!(
+                "task {} changed since it was read (commit {read}, now {current}); try again",
+                task.id
+            );
+        }
+    }
+    Jj::describe(&task.id, &task.to_description(), committer)
+}
+
+/// Resolve a change spec (could be @, a prefix, a full ID) to a change ID.
+fn resolve_change(spec: &str) -> Result<String> {
     Jj::resolve_change(spec)
 }
 
+fn parse_timestamp(ts: &str) -> Option<chrono::DateTime<Utc>> {
+    ts.parse::<chrono::DateTime<Utc>>().ok()
+}
+
+/// Author for notes written on a task: its agent, else the current agent.
+fn note_author(task: &Task) -> String {
+    task.agent
+        .clone()
+        .or_else(default_agent)
+        .unwrap_or_else(|| "unknown".into())
+}
+
+/// Result of running a task's verify command.
+struct VerifyOutcome {
+    success: bool,
+    status: String,
+    tail: String,
+}
+
+impl VerifyOutcome {
+    /// Note body recording the command, its exit status and an indented output tail.
+    fn note(&self, command: &str) -> String {
+        let mut body = format!("verify `{command}` {}", self.status);
+        for line in self.tail.lines() {
+            body.push_str("\n    ");
+            body.push_str(line);
+        }
+        body
+    }
+}
+
+const VERIFY_TAIL_LINES: usize = 20;
+/// Also capped by size, so a few huge lines cannot bloat the task description.
+const VERIFY_TAIL_BYTES: usize = 4096;
+
+/// Run `command` against the task's linked change in a temporary jj workspace,
+/// or in the current working copy when the task has no linked change.
+fn run_verify(task: &Task, command: &str) -> Result<VerifyOutcome> {
+    let (dir, workspace) = match task.change {
+        Some(ref change) => {
+            // Unique per run, so a workspace left by an interrupted run never clashes.
+            let name = format!("jjt-verify-{}-{}", task.id, std::process::id());
+            let dir = std::env::temp_dir().join(&name);
+            Jj::workspace_add(&name, &dir.to_string_lossy(), change)?;
+            (dir, Some(name))
+        }
+        None => (Jj::root()?.into(), None),
+    };
+
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(&dir)
+        .output();
+
+    if let Some(name) = workspace {
+        if let Err(e) = Jj::workspace_forget(&name) {
+            eprintln!("warning: could not forget workspace {name}: {e}");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    let output = output.with_context(|| format!("failed to run verify command `{command}`"))?;
+    let mut combined = String::from_utf8_lossy(&output.stdout).to_string();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+    let lines: Vec<&str> = combined.lines().collect();
+    let tail = lines[lines.len().saturating_sub(VERIFY_TAIL_LINES)..].join("\n");
+    let mut start = tail.len().saturating_sub(VERIFY_TAIL_BYTES);
+    while !tail.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = tail[start..].to_string();
+    let status = match output.status.code() {
+        Some(code) => format!("exited with status {code}"),
+        None => "was killed by a signal".to_string(),
+    };
+
+    Ok(VerifyOutcome {
+        success: output.status.success(),
+        status,
+        tail,
+    })
+}
+
+/// How the board's `blocked_by` entries resolve.
+struct Blockers {
+    /// Tasks with at least one blocker that is not done.
+    blocked: HashSet<String>,
+    /// Per task, blockers that are neither on the board nor decayed.
+    dangling: HashMap<String, Vec<String>>,
+}
+
+/// Resolve every blocker against the board. A blocker that is no longer on
+/// the board counts as done if it was decayed; otherwise it is dangling and
+/// keeps blocking until someone unblocks or prunes it.
+fn blocker_status(tasks: &[Task]) -> Result<Blockers> {
+    let on_board: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let missing: HashSet<&str> = tasks
+        .iter()
+        .flat_map(|t| t.blocked_by.iter().map(String::as_str))
+        .filter(|dep| !on_board.contains(dep))
+        .collect();
+    let decayed = if missing.is_empty() {
+        HashSet::new()
+    } else {
+        decayed_ids(&missing)?
+    };
+    let satisfied: HashSet<&str> = tasks
+        .iter()
+        .filter(|t| t.status == Status::Done)
+        .map(|t| t.id.as_str())
+        .chain(decayed.iter().map(String::as_str))
+        .collect();
+
+    let mut blockers = Blockers {
+        blocked: HashSet::new(),
+        dangling: HashMap::new(),
+    };
+    let mut revs: HashMap<&RevBlocker, bool> = HashMap::new();
+    for task in tasks.iter().filter(|t| t.status != Status::Done) {
+        for blocker in &task.rev_blockers {
+            let holds = *revs
+                .entry(blocker)
+                .or_insert_with(|| rev_blocker_holds(blocker));
+            if !holds {
+                blockers.blocked.insert(task.id.clone());
+            }
+        }
+    }
+    for task in tasks {
+        for dep in &task.blocked_by {
+            if satisfied.contains(dep.as_str()) {
+                continue;
+            }
+            blockers.blocked.insert(task.id.clone());
+            if missing.contains(dep.as_str()) {
+                blockers
+                    .dangling
+                    .entry(task.id.clone())
+                    .or_default()
+                    .push(dep.clone());
+            }
+        }
+    }
+    Ok(blockers)
+}
+
+/// Whether a revset blocker is satisfied. A revset jj can no longer evaluate
+/// (e.g. an abandoned change) keeps blocking, with a warning.
+fn rev_blocker_holds(blocker: &RevBlocker) -> bool {
+    let revset = &blocker.revset;
+    let size = |r: &str| match Jj::revset_size(r) {
+        Ok(n) => Some(n),
+        Err(e) => {
+            eprintln!("warning: rev_blocker {blocker}: {e}");
+            None
+        }
+    };
+    let Some(total) = size(revset) else {
+        return false;
+    };
+    let outstanding = match blocker.condition {
+        RevCondition::Landed => size(&format!("({revset}) ~ ::trunk()")),
+        RevCondition::ConflictFree => size(&format!("({revset}) & conflicts()")),
+        RevCondition::Exists => Some(0),
+    };
+    total > 0 && outstanding == Some(0)
+}
+
+/// Which of `ids` belong to decayed tasks: those recorded in the archive, then
+/// (for tasks decayed before the archive existed) done tasks abandoned in the op
+/// log, which are then added to the archive.
+fn decayed_ids(ids: &HashSet<&str>) -> Result<HashSet<String>> {
+    let archive = Jj::archive_description()?
+        .map(|d| Archive::from_description(&d))
+        .unwrap_or_default();
+    let mut decayed: HashSet<String> = archive
+        .decayed
+        .into_iter()
+        .filter(|id| ids.contains(id.as_str()))
+        .collect();
+    if decayed.len() < ids.len() {
+        let mut found = Vec::new();
+        let records = Jj::abandoned_task_records()
+            .context("could not search the op log for decayed tasks")?;
+        for record in records {
+            if let Ok(task) = task_from_record(record) {
+                if task.status == Status::Done
+                    && ids.contains(task.id.as_str())
+                    && decayed.insert(task.id.clone())
+                {
+                    found.push(task.id);
+                }
+            }
+        }
+        if !found.is_empty() {
+            // Record them, so the op log is searched for each task only once.
+            let _lock = RepoLock::acquire()?;
+            let mut archive = Jj::archive_description()?
+                .map(|d| Archive::from_description(&d))
+                .unwrap_or_default();
+            for id in found {
+                if !archive.decayed.contains(&id) {
+                    archive.decayed.push(id);
+                }
+            }
+            Jj::write_archive(&archive.to_description())?;
+        }
+    }
+    Ok(decayed)
+}
+
+fn check_label(label: &str) -> Result<()> {
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        bail!("invalid label {label:?}: labels must be non-empty and contain no spaces");
+    }
+    Ok(())
+}
+
+/// A verify command is stored as one header line.
+fn check_verify(command: &str) -> Result<()> {
+    if command.contains(['\n', '\r']) {
+        bail!("invalid verify command {command:?}: it must be a single line");
+    }
+    Ok(())
+}
+
+/// Every task on the board, from the index when it is current for this operation.
 fn load_all_tasks() -> Result<Vec<Task>> {
+    let op_id = Jj::current_op_id().ok();
+    if let Some(tasks) = op_id.as_deref().and_then(index::load) {
+        return Ok(tasks);
+    }
     let records = Jj::list_task_records()?;
     let mut tasks = Vec::new();
-    for (id, desc) in records {
-        match Task::from_description(id, &desc) {
+    for record in records {
+        match task_from_record(record) {
             Ok(task) => tasks.push(task),
             Err(e) => eprintln!("warning: skipping malformed task: {e}"),
         }
     }
+    if let Some(op_id) = op_id {
+        index::store(&op_id, &tasks);
+    }
     Ok(tasks)
 }
 
@@ -237,7 +1319,21 @@ fn cmd_init(json: bool) -> Result<()> {
     Ok(())
 }
 
-fn cmd_new(summary: String, priority: u8, change: Option<String>, json: bool) -> Result<()> {
+fn cmd_new(
+    summary: String,
+    priority: u8,
+    estimate: Option<u32>,
+    change: Option<String>,
+    verify: Option<String>,
+    labels: Vec<String>,
+    json: bool,
+) -> Result<()> {
+    for label in &labels {
+        check_label(label)?;
+    }
+    if let Some(ref command) = verify {
+        check_verify(command)?;
+    }
     // Resolve change spec if provided
     let change = match change {
         Some(spec) => Some(resolve_change(&spec)?),
@@ -249,16 +1345,33 @@ fn cmd_new(summary: String, priority: u8, change: Option<String>, json: bool) ->
         status: Status::Open,
         summary,
         priority,
+        estimate,
+        labels,
         agent: None,
+        lease_until: None,
+        assignees: vec![],
+        reviewers: vec![],
+        watchers: vec![],
         change,
         done_at: None,
+        verify,
         blocked_by: vec![],
+        rev_blockers: vec![],
+        after: vec![],
         links: vec![],
+        effects: vec![],
+        checklist: vec![],
         notes: vec![],
+        created_by: None,
+        created_at: None,
+        updated_at: None,
+        commit_id: None,
     };
-    let change_id = Jj::create_child(&task.to_description())?;
+    let creator = current_identity();
+    let change_id = Jj::create_child(&task.to_description(), creator.as_ref())?;
     let task = Task {
         id: change_id,
+        created_by: creator.map(|c| c.to_string()),
         ..task
     };
 
@@ -270,272 +1383,1358 @@ fn cmd_new(summary: String, priority: u8, change: Option<String>, json: bool) ->
     Ok(())
 }
 
-fn cmd_list(
-    ready: bool,
-    blocked: bool,
-    mine: bool,
-    done: bool,
-    all: bool,
+fn cmd_list(filter: &ListFilter, format: ListFormat, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let blockers = blocker_status(&tasks)?;
+    let now = Utc::now();
+
+    let rows: Vec<RowView> = filter
+        .select(&tasks, &blockers.blocked)?
+        .into_iter()
+        .map(|t| RowView {
+            task: t,
+            is_blocked: blockers.blocked.contains(&t.id),
+            dangling: blockers.dangling.get(&t.id).map_or(&[], Vec::as_slice),
+        })
+        .collect();
+    let mut filtered: Vec<&RowView> = rows.iter().collect();
+
+    // Ready work defaults to the order `next` would pick it in.
+    let sort = format.sort.or(filter.ready.then_some(SortKey::Priority));
+    match sort {
+        Some(SortKey::Id) => filtered.sort_by(|a, b| a.task.id.cmp(&b.task.id)),
+        Some(SortKey::Status) => {
+            filtered.sort_by_key(|r| (r.task.status as u8, r.task.priority));
+        }
+        // Timestamps carry the writer's UTC offset, so compare them as instants.
+        Some(SortKey::Created) => {
+            filtered.sort_by_cached_key(|r| r.task.created_at.as_deref().and_then(parse_timestamp))
+        }
+        Some(SortKey::Updated) => {
+            filtered.sort_by_cached_key(|r| r.task.updated_at.as_deref().and_then(parse_timestamp))
+        }
+        Some(SortKey::Priority) => {
+            filtered.sort_by_cached_key(|r| {
+                let created = r.task.created_at.as_deref().and_then(parse_timestamp);
+                (r.task.priority, created)
+            });
+            let order = graph::schedule(filtered.iter().map(|r| r.task).collect(), &tasks);
+            filtered = order
+                .iter()
+                .filter_map(|t| filtered.iter().find(|r| r.task.id == t.id).copied())
+                .collect();
+        }
+        Some(SortKey::Unblocks) => {
+            let graph = DepGraph::from_tasks(tasks.iter().filter(|t| t.status != Status::Done));
+            filtered.sort_by_key(|r| {
+                let unblocks = graph.downstream(&r.task.id).len();
+                (std::cmp::Reverse(unblocks), r.task.priority)
+            });
+        }
+        None => {}
+    }
+    if format.reverse {
+        filtered.reverse();
+    }
+    if let Some(limit) = format.limit {
+        filtered.truncate(limit);
+    }
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct JsonRow<'a> {
+            #[serde(flatten)]
+            task: &'a Task,
+            is_blocked: bool,
+            #[serde(skip_serializing_if = "<[String]>::is_empty")]
+            dangling_blockers: &'a [String],
+        }
+        let json_rows: Vec<JsonRow> = filtered
+            .iter()
+            .map(|r| JsonRow {
+                task: r.task,
+                is_blocked: r.is_blocked,
+                dangling_blockers: r.dangling,
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&json_rows)?);
+        return Ok(());
+    }
+
+    if filtered.is_empty() {
+        println!("no tasks");
+        return Ok(());
+    }
+    let ids = id_prefixes(&tasks);
+    let lines: Vec<String> = match format.template {
+        Some(ref template) => {
+            let template: Template = template.parse().context("invalid --template")?;
+            filtered
+                .iter()
+                .map(|r| template.render(r, &ids, now))
+                .collect()
+        }
+        None => {
+            let columns = format.columns.as_deref().unwrap_or(layout::DEFAULT_COLUMNS);
+            let columns = Column::parse_list(columns).context("invalid --columns")?;
+            layout::table(&filtered, &columns, &ids, now)
+        }
+    };
+    let width = terminal_width();
+    // Templates may span several lines with \n; each is cut separately.
+    for line in lines.iter().flat_map(|l| l.lines()) {
+        match width {
+            Some(width) => println!("{}", layout::truncate(line, width)),
+            None => println!("{line}"),
+        }
+    }
+    Ok(())
+}
+
+/// Width to cut list lines to: `$COLUMNS`, else the terminal's, else none
+/// (output is piped).
+fn terminal_width() -> Option<usize> {
+    if let Some(width) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(width);
+    }
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+}
+
+fn cmd_search(
+    terms: &[String],
+    status: Option<Status>,
+    labels: &[String],
     json: bool,
 ) -> Result<()> {
+    let board = load_all_tasks()?;
+    let mut tasks = board.clone();
+    tasks.retain(|t| status.is_none_or(|s| t.status == s));
+    tasks.retain(|t| labels.iter().all(|l| t.labels.contains(l)));
+    let hits = search::search(&tasks, terms);
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct JsonHit<'a> {
+            id: &'a str,
+            summary: &'a str,
+            status: Status,
+            priority: u8,
+            #[serde(flatten)]
+            hit: &'a search::Hit<'a>,
+        }
+        let rows: Vec<JsonHit> = hits
+            .iter()
+            .map(|h| JsonHit {
+                id: &h.task.id,
+                summary: &h.task.summary,
+                status: h.task.status,
+                priority: h.task.priority,
+                hit: h,
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&rows)?);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        println!("no matches");
+        return Ok(());
+    }
+    let color = std::io::stdout().is_terminal();
+    let ids = id_prefixes(&board);
+    for h in &hits {
+        let t = h.task;
+        println!(
+            "{} {:<8} p{}  {}",
+            ids.show(&t.id, 13),
+            t.status,
+            t.priority,
+            t.summary
+        );
+        let mut snippet = h.snippet.clone();
+        if color {
+            for &(start, end) in h.highlights.iter().rev() {
+                snippet.insert_str(end, "\x1b[0m");
+                snippet.insert_str(start, "\x1b[1;33m");
+            }
+        }
+        println!("    {}: {snippet}", h.field);
+    }
+    Ok(())
+}
+
+fn cmd_graph(filter: &ListFilter, format: GraphFormat, changes: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let blocked = blocker_status(&tasks)?.blocked;
+    let board = Board {
+        tasks: filter.select(&tasks, &blocked)?,
+        blocked: &blocked,
+        changes,
+    };
+    match format {
+        GraphFormat::Dot => print!("{}", board.dot()),
+        GraphFormat::Mermaid => print!("{}", board.mermaid()),
+    }
+    Ok(())
+}
+
+fn cmd_show(id: &str, notes: Option<NoteKind>, json: bool) -> Result<()> {
     let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    if let Some(kind) = notes {
+        task.notes.retain(|n| n.kind == Some(kind));
+    }
+    let backlinks = Backlinks::of(&task.id, &tasks);
+    if json {
+        #[derive(serde::Serialize)]
+        struct Shown<'a> {
+            #[serde(flatten)]
+            task: &'a Task,
+            backlinks: &'a Backlinks,
+        }
+        let shown = Shown {
+            task: &task,
+            backlinks: &backlinks,
+        };
+        println!("{}", serde_json::to_string_pretty(&shown)?);
+    } else {
+        println!("id: {}", id_prefixes(&tasks).show(&task.id, 0));
+        print!("{}", task.to_headers());
+        for (name, ids) in backlinks.entries() {
+            if !ids.is_empty() {
+                println!("{name}: {}", ids.join(" "));
+            }
+        }
+        let ids: HashSet<u32> = task.notes.iter().map(|n| n.id).collect();
+        for note in &task.notes {
+            // Replies whose parent was filtered out are shown at the top level.
+            if note.reply_to.is_none_or(|p| !ids.contains(&p)) {
+                print_note_thread(&task, note, 0);
+            }
+        }
+    }
+    Ok(())
+}
 
-    let done_ids: HashSet<&str> = tasks
+fn print_note_thread(task: &Task, note: &Note, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let kind = note.kind.map(|k| format!(" [{k}]")).unwrap_or_default();
+    let edited = if note.edited_at.is_some() {
+        " (edited)"
+    } else {
+        ""
+    };
+    let origin = note
+        .origin
+        .as_ref()
+        .map(|o| format!(" (from {o})"))
+        .unwrap_or_default();
+    println!(
+        "\n{indent}--- #{} {} {}{kind}{edited}{origin}",
+        note.id, note.author, note.timestamp
+    );
+    if note.deleted_at.is_some() {
+        println!("{indent}[deleted]");
+    } else {
+        for line in note.body.lines() {
+            println!("{indent}{line}");
+        }
+    }
+    for reply in task.notes.iter().filter(|n| n.reply_to == Some(note.id)) {
+        print_note_thread(task, reply, depth + 1);
+    }
+}
+
+/// Lease expiry for a claim made now: `--lease`, else `jjt.claim.lease`, else none.
+fn lease_expiry(lease: Option<String>) -> Result<Option<String>> {
+    let lease = lease.or_else(|| Config::load().get("jjt.claim.lease").map(String::from));
+    match lease {
+        Some(age) => Ok(Some((Utc::now() + query::parse_age(&age)?).to_rfc3339())),
+        None => Ok(None),
+    }
+}
+
+fn cmd_claim(
+    id: &str,
+    agent: Option<String>,
+    lease: Option<String>,
+    steal: bool,
+    json: bool,
+) -> Result<()> {
+    let agent = resolve_agent(agent)?;
+    let lease_until = lease_expiry(lease)?;
+
+    let _lock = RepoLock::acquire()?;
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    check_wip(&tasks, &agent, &task.id)?;
+    claim_task(&mut task, &agent, lease_until, steal)?;
+    save_task_as(&task, &agent)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} claimed by {}", task.id, agent);
+    }
+    Ok(())
+}
+
+/// Claim `task` for `agent`, taking it from its current holder if `steal`.
+/// Callers hold the repo lock and save the task.
+fn claim_task(
+    task: &mut Task,
+    agent: &str,
+    lease_until: Option<String>,
+    steal: bool,
+) -> Result<()> {
+    if task.status == Status::Done {
+        bail!("task {} is already done", task.id);
+    }
+    let held_by_other = task.status == Status::Claimed
+        && !task
+            .agent
+            .as_deref()
+            .is_some_and(|a| Identity::parse(agent).matches(a));
+    if steal && held_by_other && !task.claim_expired(Utc::now()) {
+        let previous = task.agent.clone().unwrap_or_else(|| "unknown".into());
+        task.add_note(
+            agent.to_string(),
+            Some(NoteKind::Handoff),
+            format!("handoff: stolen from {previous} by {agent}"),
+        );
+    } else if task.claim_expired(Utc::now()) {
+        let previous = task.agent.clone().unwrap_or_else(|| "unknown".into());
+        let expired = task.lease_until.clone().unwrap_or_default();
+        task.add_note(
+            agent.to_string(),
+            Some(NoteKind::Log),
+            format!("claim by {previous} expired at {expired}; reclaimed by {agent}"),
+        );
+    } else if task.status == Status::Claimed {
+        // With `jjt.claim.exclusive = false`, later claimers join as assignees.
+        if Config::load().get_bool("jjt.claim.exclusive", true) {
+            bail!(
+                "task {} is already claimed by {}",
+                task.id,
+                task.agent.as_deref().unwrap_or("unknown")
+            );
+        }
+        let who = Identity::parse(agent);
+        let holds = task.agent.as_deref().is_some_and(|a| who.matches(a))
+            || task.assignees.iter().any(|a| who.matches(a));
+        if !holds {
+            task.assignees.push(agent.to_string());
+        }
+        return Ok(());
+    }
+    task.status = Status::Claimed;
+    task.agent = Some(agent.to_string());
+    task.lease_until = lease_until;
+    Ok(())
+}
+
+/// Work-in-progress limits from config: `jjt.wip.agent` (default per agent),
+/// `jjt.wip.agents.<name or email>` (per-agent override) and `jjt.wip.claimed`
+/// (claimed tasks on the whole board).
+struct WipLimits {
+    per_agent: Option<u32>,
+    agents: Vec<(Identity, u32)>,
+    claimed: Option<u32>,
+}
+
+impl WipLimits {
+    fn load() -> WipLimits {
+        let config = Config::load();
+        WipLimits {
+            per_agent: config.get_u32("jjt.wip.agent"),
+            agents: config
+                .entries("jjt.wip.agents")
+                .filter_map(|(who, n)| Some((Identity::parse(who), n.parse().ok()?)))
+                .collect(),
+            claimed: config.get_u32("jjt.wip.claimed"),
+        }
+    }
+
+    fn for_agent(&self, agent: &str) -> Option<u32> {
+        self.agents
+            .iter()
+            .find(|(who, _)| who.matches(agent))
+            .map(|&(_, n)| n)
+            .or(self.per_agent)
+    }
+}
+
+/// Tasks actively claimed (lease not expired).
+fn active_claims(tasks: &[Task]) -> impl Iterator<Item = &Task> {
+    let now = Utc::now();
+    tasks
         .iter()
-        .filter(|t| t.status == Status::Done)
-        .map(|t| t.id.as_str())
+        .filter(move |t| t.status == Status::Claimed && !t.claim_expired(now))
+}
+
+/// Number of active claims held by `agent`, as claimant or assignee.
+fn wip_load<'a>(tasks: impl Iterator<Item = &'a Task>, agent: &str) -> u32 {
+    let who = Identity::parse(agent);
+    tasks
+        .filter(|t| {
+            t.agent.as_deref().is_some_and(|a| who.matches(a))
+                || t.assignees.iter().any(|a| who.matches(a))
+        })
+        .count() as u32
+}
+
+/// Refuse a claim of `task_id` by `agent` that would exceed a WIP limit.
+fn check_wip(tasks: &[Task], agent: &str, task_id: &str) -> Result<()> {
+    let limits = WipLimits::load();
+    let others = || active_claims(tasks).filter(|t| t.id != task_id);
+    if let Some(limit) = limits.for_agent(agent) {
+        let load = wip_load(others(), agent);
+        if load >= limit {
+            bail!("{agent} already has {load} claimed task(s) (WIP limit {limit})");
+        }
+    }
+    if let Some(limit) = limits.claimed {
+        let load = others().count() as u32;
+        if load >= limit {
+            bail!("board already has {load} claimed task(s) (WIP limit {limit})");
+        }
+    }
+    Ok(())
+}
+
+/// Exit status of `jjt next` when no task is ready.
+const EXIT_NOTHING_READY: i32 = 3;
+
+fn cmd_next(
+    claim: bool,
+    labels: &[String],
+    max_priority: Option<u8>,
+    agent: Option<String>,
+    lease: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let agent = resolve_agent(agent)?;
+    let lease_until = lease_expiry(lease)?;
+
+    // Hold the lock across select-and-claim so concurrent workers never pick the same task.
+    let _lock = if claim {
+        Some(RepoLock::acquire()?)
+    } else {
+        None
+    };
+    let tasks = load_all_tasks()?;
+    let blocked = blocker_status(&tasks)?.blocked;
+    let now = Utc::now();
+
+    let mut ready: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| t.is_claimable(now) && !blocked.contains(&t.id))
+        .filter(|t| labels.iter().all(|l| t.labels.contains(l)))
+        .filter(|t| max_priority.is_none_or(|max| t.priority <= max))
         .collect();
+    ready.sort_by_key(|t| {
+        (
+            t.priority,
+            t.created_at.as_deref().and_then(parse_timestamp),
+        )
+    });
+    let ready = graph::schedule(ready, &tasks);
+
+    let Some(&next) = ready.first() else {
+        if json {
+            println!("null");
+        } else {
+            eprintln!("no ready tasks");
+        }
+        return Err(ExitStatus(EXIT_NOTHING_READY).into());
+    };
+
+    let mut task = next.clone();
+    if claim {
+        check_wip(&tasks, &agent, &task.id)?;
+        claim_task(&mut task, &agent, lease_until, false)?;
+        save_task_as(&task, &agent)?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        let id = id_prefixes(&tasks).show(&task.id, 0);
+        if claim {
+            println!("{id} claimed by {agent}: {}", task.summary);
+        } else {
+            println!("{id} p{}  {}", task.priority, task.summary);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_label(id: &str, labels: Vec<String>, remove: bool, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    for label in labels {
+        check_label(&label)?;
+        if remove {
+            task.labels.retain(|l| l != &label);
+        } else if !task.labels.contains(&label) {
+            task.labels.push(label);
+        }
+    }
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} labels: {}", task.id, task.labels.join(" "));
+    }
+    Ok(())
+}
+
+fn cmd_estimate(id: &str, estimate: Option<u32>, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    task.estimate = estimate;
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        match estimate {
+            Some(e) => println!("{} estimate: {e}", task.id),
+            None => println!("{} estimate cleared", task.id),
+        }
+    }
+    Ok(())
+}
+
+fn cmd_release(id: &str, agent: Option<String>, note: Option<String>, json: bool) -> Result<()> {
+    let agent = resolve_agent(agent)?;
+    let _lock = RepoLock::acquire()?;
+    let mut task = load_task(id)?;
+    if task.status != Status::Claimed {
+        bail!("task {} is not claimed", task.id);
+    }
+    let who = Identity::parse(&agent);
+    let co_claimer = task.assignees.iter().position(|a| who.matches(a));
+    let holds = task.agent.as_deref().is_some_and(|a| who.matches(a));
+    let previous = match co_claimer {
+        // A co-claimer (see jjt.claim.exclusive) only drops out; the claim stays.
+        Some(pos) if !holds => task.assignees.remove(pos),
+        _ => {
+            check_claim_holder(&task, &agent)?;
+            task.status = Status::Open;
+            task.lease_until = None;
+            task.agent.take().unwrap_or_else(|| "unknown".into())
+        }
+    };
+    let mut body = format!("handoff: released by {previous}");
+    if let Some(note) = note {
+        body.push('\n');
+        body.push_str(&note);
+    }
+    task.add_note(agent, Some(NoteKind::Handoff), body);
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} released by {}", task.id, previous);
+    }
+    Ok(())
+}
+
+fn cmd_handoff(
+    id: &str,
+    agent: Option<String>,
+    to: &str,
+    note: &str,
+    lease: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let agent = resolve_agent(agent)?;
+    check_agent(to)?;
+    let lease_until = lease_expiry(lease)?;
+    let _lock = RepoLock::acquire()?;
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    if task.status == Status::Done {
+        bail!("task {} is already done", task.id);
+    }
+    // An active claim is passed on only by its holder; others use `claim --steal`.
+    if task.status == Status::Claimed && !task.claim_expired(Utc::now()) {
+        check_claim_holder(&task, &agent)?;
+    }
+    check_wip(&tasks, to, &task.id)?;
+    let from = task.agent.clone().unwrap_or_else(|| agent.clone());
+    let by = if Identity::parse(&agent).matches(&from) {
+        String::new()
+    } else {
+        format!(" by {agent}")
+    };
+    task.add_note(
+        agent,
+        Some(NoteKind::Handoff),
+        format!("handoff: from {from} to {to}{by}\n{note}"),
+    );
+    task.status = Status::Claimed;
+    task.agent = Some(to.to_string());
+    task.lease_until = lease_until;
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} handed off from {} to {}", task.id, from, to);
+    }
+    Ok(())
+}
+
+fn cmd_heartbeat(id: &str, agent: Option<String>, lease: Option<String>, json: bool) -> Result<()> {
+    let agent = resolve_agent(agent)?;
+    // Read under the lock, so a concurrent reap or steal is not undone.
+    let _lock = RepoLock::acquire()?;
+    let mut task = load_task(id)?;
+    if task.status != Status::Claimed {
+        bail!("task {} is not claimed", task.id);
+    }
+    check_claim_holder(&task, &agent)?;
+    let Some(lease_until) = lease_expiry(lease)? else {
+        bail!("no lease length: pass --lease or set jjt.claim.lease");
+    };
+    task.lease_until = Some(lease_until);
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!(
+            "{} lease extended until {}",
+            task.id,
+            task.lease_until.as_deref().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Fail unless the current agent holds `task`'s claim. Others take a claim
+/// over with `claim --steal` or `handoff`, which leave a note.
+fn check_claim_holder(task: &Task, agent: &str) -> Result<()> {
+    let holder = task.agent.as_deref().unwrap_or("unknown");
+    if !Identity::parse(agent).matches(holder) {
+        bail!(
+            "task {} is claimed by {holder}, not {agent} (take it over with `jjt claim --steal` or `jjt handoff`)",
+            task.id
+        );
+    }
+    Ok(())
+}
+
+fn cmd_reap(json: bool) -> Result<()> {
+    let now = Utc::now();
+    let reaper = default_agent().unwrap_or_else(|| "unknown".into());
+    let mut reaped = Vec::new();
+
+    // Read under the lock, so a claim made since is never reset.
+    let _lock = RepoLock::acquire()?;
+    for mut task in load_all_tasks()? {
+        if !task.claim_expired(now) {
+            continue;
+        }
+        let previous = task.agent.take().unwrap_or_else(|| "unknown".into());
+        let expired = task.lease_until.take().unwrap_or_default();
+        task.status = Status::Open;
+        task.add_note(
+            reaper.clone(),
+            Some(NoteKind::Log),
+            format!("claim by {previous} expired at {expired}; reset to open by jjt reap"),
+        );
+        save_task(&task)?;
+        reaped.push(task.id);
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&serde_json::json!({ "reaped": reaped }))?
+        );
+    } else if reaped.is_empty() {
+        println!("no expired claims");
+    } else {
+        println!(
+            "reaped {} expired claims: {}",
+            reaped.len(),
+            reaped.join(" ")
+        );
+    }
+    Ok(())
+}
+
+fn cmd_wip(json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let limits = WipLimits::load();
 
-    let agent = default_agent();
+    let mut agents: Vec<String> = Vec::new();
+    for t in active_claims(&tasks) {
+        for name in t.agent.iter().chain(&t.assignees) {
+            if !agents.iter().any(|a| Identity::parse(a).matches(name)) {
+                agents.push(name.clone());
+            }
+        }
+    }
+    agents.sort();
 
-    struct Row<'a> {
-        task: &'a Task,
-        is_blocked: bool,
+    #[derive(serde::Serialize)]
+    struct Load {
+        agent: String,
+        claimed: u32,
+        limit: Option<u32>,
     }
+    let loads: Vec<Load> = agents
+        .into_iter()
+        .map(|agent| Load {
+            claimed: wip_load(active_claims(&tasks), &agent),
+            limit: limits.for_agent(&agent),
+            agent,
+        })
+        .collect();
+    let total = active_claims(&tasks).count() as u32;
+
+    if json {
+        let out = serde_json::json!({
+            "agents": loads,
+            "claimed": total,
+            "limit": limits.claimed,
+        });
+        println!("{}", serde_json::to_string(&out)?);
+    } else {
+        let fmt_limit = |limit: Option<u32>| limit.map(|l| format!("/{l}")).unwrap_or_default();
+        for load in &loads {
+            println!(
+                "{:<30} {}{}",
+                load.agent,
+                load.claimed,
+                fmt_limit(load.limit)
+            );
+        }
+        println!("{:<30} {}{}", "(board)", total, fmt_limit(limits.claimed));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Assignee,
+    Reviewer,
+    Watcher,
+}
+
+fn cmd_people(id: &str, role: Role, names: Vec<String>, remove: bool, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    let (list, label) = match role {
+        Role::Assignee => (&mut task.assignees, "assignees"),
+        Role::Reviewer => (&mut task.reviewers, "reviewers"),
+        Role::Watcher => (&mut task.watchers, "watchers"),
+    };
+    for name in names {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.contains(',') {
+            bail!("invalid name {name:?}: names must be non-empty and contain no commas");
+        }
+        let who = Identity::parse(&name);
+        if remove {
+            list.retain(|n| !who.matches(n));
+        } else if !list.iter().any(|n| who.matches(n)) {
+            list.push(name);
+        }
+    }
+    let summary = list.join(", ");
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else if summary.is_empty() {
+        println!("{} {label}: none", task.id);
+    } else {
+        println!("{} {label}: {summary}", task.id);
+    }
+    Ok(())
+}
+
+fn cmd_done(
+    id: &str,
+    note: Option<String>,
+    force: bool,
+    skip_verify: bool,
+    json: bool,
+) -> Result<()> {
+    let mut task = load_task(id)?;
+    if task.status == Status::Done {
+        bail!("task {} is already done", task.id);
+    }
+    let (checked, total) = task.checklist_progress();
+    if checked < total && !force {
+        bail!(
+            "task {} has {} unchecked checklist item(s) (use --force to close anyway)",
+            task.id,
+            total - checked
+        );
+    }
+
+    if let Some(command) = task.verify.clone() {
+        let body = if skip_verify {
+            format!("verify `{command}` skipped (--skip-verify)")
+        } else {
+            let outcome = run_verify(&task, &command)?;
+            if !outcome.success {
+                task.add_note(
+                    note_author(&task),
+                    Some(NoteKind::Log),
+                    outcome.note(&command),
+                );
+                save_task(&task)?;
+                bail!(
+                    "task {} not closed: verify `{command}` {}",
+                    task.id,
+                    outcome.status
+                );
+            }
+            outcome.note(&command)
+        };
+        task.add_note(note_author(&task), Some(NoteKind::Log), body);
+    }
+
+    task.status = Status::Done;
+    task.lease_until = None;
+    task.done_at = Some(Utc::now().to_rfc3339());
+
+    if let Some(body) = note {
+        task.add_note(note_author(&task), None, body);
+    }
+
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} done", task.id);
+    }
+    Ok(())
+}
+
+fn cmd_verify(id: &str, command: Option<String>, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+
+    if let Some(command) = command {
+        check_verify(&command)?;
+        task.verify = (!command.trim().is_empty()).then_some(command);
+        save_task(&task)?;
+        if json {
+            println!("{}", serde_json::to_string(&task)?);
+        } else {
+            match task.verify {
+                Some(ref command) => println!("{} verify: {command}", task.id),
+                None => println!("{} verify cleared", task.id),
+            }
+        }
+        return Ok(());
+    }
+
+    let Some(command) = task.verify.clone() else {
+        bail!("task {} has no verify command", task.id);
+    };
+    let outcome = run_verify(&task, &command)?;
+    task.add_note(
+        note_author(&task),
+        Some(NoteKind::Log),
+        outcome.note(&command),
+    );
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} verify `{command}` {}", task.id, outcome.status);
+    }
+    if !outcome.success {
+        return Err(ExitStatus(1).into());
+    }
+    Ok(())
+}
+
+fn cmd_reopen(id: &str, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    task.status = Status::Open;
+    task.agent = None;
+    task.lease_until = None;
+    task.done_at = None;
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} reopened", task.id);
+    }
+    Ok(())
+}
+
+fn cmd_block(id: &str, on: &str, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    let blocker = find_task(on, &tasks)?;
+
+    if task.id == blocker.id {
+        bail!("a task cannot block itself");
+    }
+    if task.blocked_by.contains(&blocker.id) {
+        bail!("{} is already blocked by {}", task.id, blocker.id);
+    }
+    if let Some(cycle) = DepGraph::from_tasks(&tasks).cycle_if_blocked(&task.id, &blocker.id) {
+        bail!(
+            "blocking {} on {} would create a cycle: {}",
+            task.id,
+            blocker.id,
+            cycle.join(" -> ")
+        );
+    }
+
+    task.blocked_by.push(blocker.id.clone());
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} blocked by {}", task.id, blocker.id);
+    }
+    Ok(())
+}
+
+fn cmd_after(id: &str, on: &str, remove: bool, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    let other = if remove {
+        resolve_ref(on, &tasks, task.after.iter())?
+    } else {
+        find_task(on, &tasks)?.id.clone()
+    };
+
+    if task.id == other {
+        bail!("a task cannot come after itself");
+    }
+    if remove {
+        let before = task.after.len();
+        task.after.retain(|a| a != &other);
+        if task.after.len() == before {
+            bail!("{} is not scheduled after {}", task.id, other);
+        }
+    } else {
+        if task.after.contains(&other) {
+            bail!("{} is already scheduled after {}", task.id, other);
+        }
+        task.after.push(other.clone());
+    }
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else if remove {
+        println!("{} no longer after {}", task.id, other);
+    } else {
+        println!("{} after {}", task.id, other);
+    }
+    Ok(())
+}
 
-    let rows: Vec<Row> = tasks
-        .iter()
-        .map(|t| {
-            let is_blocked = !t.blocked_by.is_empty()
-                && t.blocked_by
-                    .iter()
-                    .any(|dep| !done_ids.contains(dep.as_str()));
-            Row {
-                task: t,
-                is_blocked,
-            }
-        })
-        .collect();
+fn cmd_deps(id: &str, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let task = find_task(id, &tasks)?;
+    let by_id: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let graph = DepGraph::from_tasks(&tasks);
+    let upstream = graph.upstream(&task.id);
+    let downstream = graph.downstream(&task.id);
 
-    let filtered: Vec<&Row> = rows
-        .iter()
-        .filter(|r| {
-            if all {
-                return true;
-            }
-            if ready {
-                return r.task.status == Status::Open && !r.is_blocked;
-            }
-            if blocked {
-                return r.task.status == Status::Open && r.is_blocked;
-            }
-            if mine {
-                return r.task.status == Status::Claimed
-                    && r.task.agent.as_deref() == agent.as_deref();
+    if json {
+        let entries = |deps: &[(&str, usize)]| -> Vec<serde_json::Value> {
+            deps.iter()
+                .map(|&(dep, depth)| {
+                    let status = by_id.get(dep).map(|t| t.status.to_string());
+                    serde_json::json!({ "id": dep, "depth": depth, "status": status })
+                })
+                .collect()
+        };
+        let out = serde_json::json!({
+            "id": task.id,
+            "upstream": entries(&upstream),
+            "downstream": entries(&downstream),
+        });
+        println!("{}", serde_json::to_string(&out)?);
+    } else {
+        let ids = id_prefixes(&tasks);
+        let print = |deps: &[(&str, usize)]| {
+            if deps.is_empty() {
+                println!("  (none)");
             }
-            if done {
-                return r.task.status == Status::Done;
+            for &(dep, depth) in deps {
+                let indent = "  ".repeat(depth);
+                match by_id.get(dep) {
+                    Some(t) => {
+                        println!(
+                            "{indent}{} {:<8} {}",
+                            ids.show(&t.id, 13),
+                            t.status,
+                            t.summary
+                        )
+                    }
+                    None => println!("{indent}{dep:<13} missing"),
+                }
             }
-            r.task.status != Status::Done
-        })
-        .collect();
+        };
+        println!("{} waits on:", ids.show(&task.id, 0));
+        print(&upstream);
+        println!("waiting on {}:", ids.show(&task.id, 0));
+        print(&downstream);
+    }
+    Ok(())
+}
+
+fn cmd_critical_path(id: Option<&str>, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let target = id.map(|id| find_task(id, &tasks)).transpose()?;
+    let by_id: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let graph = DepGraph::from_tasks(tasks.iter().filter(|t| t.status != Status::Done));
+    let weight = |id: &str| by_id.get(id).and_then(|t| t.estimate).unwrap_or(1);
+    let (path, total) = match target {
+        Some(t) if t.status == Status::Done => (vec![], 0),
+        Some(t) => graph.critical_path(Some(&t.id), weight),
+        None => graph.critical_path(None, weight),
+    };
 
     if json {
-        #[derive(serde::Serialize)]
-        struct JsonRow<'a> {
-            #[serde(flatten)]
-            task: &'a Task,
-            is_blocked: bool,
-        }
-        let json_rows: Vec<JsonRow> = filtered
-            .iter()
-            .map(|r| JsonRow {
-                task: r.task,
-                is_blocked: r.is_blocked,
-            })
-            .collect();
-        println!("{}", serde_json::to_string(&json_rows)?);
+        let out = serde_json::json!({ "path": path, "total": total });
+        println!("{}", serde_json::to_string(&out)?);
+    } else if path.is_empty() {
+        println!("nothing left to do");
     } else {
-        if filtered.is_empty() {
-            println!("no tasks");
-            return Ok(());
-        }
-        for r in &filtered {
-            let t = r.task;
-            let status_str = if r.is_blocked && t.status == Status::Open {
-                "blocked"
-            } else {
-                match t.status {
-                    Status::Open => "open",
-                    Status::Claimed => "claimed",
-                    Status::Done => "done",
-                }
-            };
-            let agent_str = t
-                .agent
-                .as_ref()
-                .map(|a| format!("  [{a}]"))
-                .unwrap_or_default();
-            let change_str = t
-                .change
-                .as_ref()
-                .map(|c| format!("  @{c}"))
-                .unwrap_or_default();
+        let ids = id_prefixes(&tasks);
+        for id in &path {
+            let t = by_id[id];
+            let estimate = t.estimate.map(|e| format!("  ~{e}")).unwrap_or_default();
             println!(
-                "{:<13} {:<8} p{}  {}{agent_str}{change_str}",
-                t.id, status_str, t.priority, t.summary
+                "{} {:<8} {}{estimate}",
+                ids.show(&t.id, 13),
+                t.status,
+                t.summary
             );
         }
+        println!("total: {total}");
     }
     Ok(())
 }
 
-fn cmd_show(id: &str, json: bool) -> Result<()> {
-    let task = load_task(id)?;
+fn cmd_block_rev(id: &str, revset: String, condition: RevCondition, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    let revset = pin_revision(revset)?;
+    let blocker = RevBlocker { condition, revset };
+    if task.rev_blockers.contains(&blocker) {
+        bail!("{} is already blocked until {blocker}", task.id);
+    }
+    task.rev_blockers.push(blocker.clone());
+    save_task(&task)?;
+
     if json {
-        println!("{}", serde_json::to_string_pretty(&task)?);
+        println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("id: {}", task.id);
-        print!("{}", task.to_description());
+        println!("{} blocked until {blocker}", task.id);
     }
     Ok(())
 }
 
-fn cmd_claim(id: &str, agent: Option<String>, json: bool) -> Result<()> {
+/// Check a blocking revset, pinning it to a change ID when it names a single
+/// revision: `@` or `@-` would otherwise mean another change whenever the
+/// working copy moves, and a different one in every workspace.
+fn pin_revision(revset: String) -> Result<String> {
+    // Evaluate once so a typo fails here rather than blocking forever.
+    let size = Jj::revset_size(&revset).with_context(|| format!("invalid revset {revset:?}"))?;
+    if size == 1 {
+        Jj::resolve_change(&revset)
+    } else {
+        Ok(revset)
+    }
+}
+
+fn cmd_unblock_rev(id: &str, revset: &str, json: bool) -> Result<()> {
     let mut task = load_task(id)?;
-    let agent = agent
-        .or_else(default_agent)
-        .unwrap_or_else(|| "unknown".into());
+    // Accept the revset as typed at `block` time too, e.g. `@` for its pinned change.
+    let pinned = if task.rev_blockers.iter().any(|b| b.revset == revset) {
+        revset.to_string()
+    } else {
+        pin_revision(revset.to_string()).unwrap_or_else(|_| revset.to_string())
+    };
+    let revset = pinned.as_str();
+    let before = task.rev_blockers.len();
+    task.rev_blockers.retain(|b| b.revset != revset);
+    if task.rev_blockers.len() == before {
+        bail!("{} is not blocked on revset {revset}", task.id);
+    }
+    save_task(&task)?;
 
-    if task.status == Status::Done {
-        bail!("task {} is already done", task.id);
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} unblocked from revset {revset}", task.id);
     }
-    if task.status == Status::Claimed {
-        bail!(
-            "task {} is already claimed by {}",
-            task.id,
-            task.agent.as_deref().unwrap_or("unknown")
-        );
+    Ok(())
+}
+
+fn cmd_unblock(id: &str, from: &str, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    let blocker = resolve_ref(from, &tasks, task.blocked_by.iter())?;
+
+    let before = task.blocked_by.len();
+    task.blocked_by.retain(|b| b != &blocker);
+    if task.blocked_by.len() == before {
+        bail!("{} is not blocked by {}", task.id, blocker);
     }
 
-    task.status = Status::Claimed;
-    task.agent = Some(agent.clone());
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("{} claimed by {}", task.id, agent);
+        println!("{} unblocked from {}", task.id, blocker);
     }
     Ok(())
 }
 
-fn cmd_done(id: &str, note: Option<String>, json: bool) -> Result<()> {
+fn cmd_note(
+    id: &str,
+    body: &str,
+    author: Option<String>,
+    kind: Option<NoteKind>,
+    reply_to: Option<u32>,
+    json: bool,
+) -> Result<()> {
     let mut task = load_task(id)?;
-    if task.status == Status::Done {
-        bail!("task {} is already done", task.id);
+    if let Some(parent) = reply_to {
+        if !task.notes.iter().any(|n| n.id == parent) {
+            bail!("{} has no note #{}", task.id, parent);
+        }
+    }
+    if let Some(ref author) = author {
+        check_agent(author)?;
     }
+    let actor = author.clone();
+    let author = author
+        .or_else(|| task.agent.clone())
+        .or_else(default_agent)
+        .unwrap_or_else(|| "unknown".into());
 
-    task.status = Status::Done;
-    task.done_at = Some(Utc::now().to_rfc3339());
+    let note = task.add_note(author, kind, body.to_string());
+    note.reply_to = reply_to;
+    let note_id = note.id;
+    match actor {
+        Some(actor) => save_task_as(&task, &actor)?,
+        None => save_task(&task)?,
+    }
 
-    if let Some(body) = note {
-        let author = task
-            .agent
-            .clone()
-            .or_else(default_agent)
-            .unwrap_or_else(|| "unknown".into());
-        task.notes.push(Note {
-            author,
-            timestamp: Utc::now().to_rfc3339(),
-            body,
-        });
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("noted on {} (#{note_id})", task.id);
     }
+    Ok(())
+}
 
+fn cmd_note_edit(id: &str, note_id: u32, body: String, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    let task_id = task.id.clone();
+    let Some(note) = task.note_mut(note_id) else {
+        bail!("{task_id} has no note #{note_id}");
+    };
+    if note.deleted_at.is_some() {
+        bail!("note #{note_id} on {task_id} was deleted");
+    }
+    note.body = body;
+    note.edited_at = Some(Utc::now().to_rfc3339());
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("{} done", task.id);
+        println!("edited note #{note_id} on {task_id}");
     }
     Ok(())
 }
 
-fn cmd_reopen(id: &str, json: bool) -> Result<()> {
+fn cmd_note_rm(id: &str, note_id: u32, json: bool) -> Result<()> {
     let mut task = load_task(id)?;
-    task.status = Status::Open;
-    task.agent = None;
-    task.done_at = None;
+    let task_id = task.id.clone();
+    let Some(note) = task.note_mut(note_id) else {
+        bail!("{task_id} has no note #{note_id}");
+    };
+    if note.deleted_at.is_some() {
+        bail!("note #{note_id} on {task_id} is already deleted");
+    }
+    // Keep a tombstone so replies stay attached; the old body lives on in `jjt log`.
+    note.body.clear();
+    note.deleted_at = Some(Utc::now().to_rfc3339());
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("{} reopened", task.id);
+        println!("deleted note #{note_id} on {task_id}");
     }
     Ok(())
 }
 
-fn cmd_block(id: &str, on: &str, json: bool) -> Result<()> {
-    let mut task = load_task(id)?;
-    // Verify the blocker is a valid task
-    let blocker = load_task(on)?;
+fn cmd_log(id: &str, json: bool) -> Result<()> {
+    let task = load_task(id)?;
+    let records = Jj::evolog_records(&task.id)?;
 
-    if task.id == blocker.id {
-        bail!("a task cannot block itself");
+    #[derive(serde::Serialize)]
+    struct Entry {
+        commit_id: String,
+        timestamp: String,
+        changes: Vec<String>,
     }
-    if task.blocked_by.contains(&blocker.id) {
-        bail!("{} is already blocked by {}", task.id, blocker.id);
+
+    let mut entries = Vec::new();
+    let mut previous: Option<Task> = None;
+    for (header, desc) in records {
+        let (commit_id, timestamp) = header.split_once(' ').unwrap_or((&header, ""));
+        let Ok(version) = Task::from_description(task.id.clone(), &desc) else {
+            continue;
+        };
+        let changes = match previous {
+            Some(ref prev) => prev.diff(&version),
+            None => vec![format!("created: {}", version.summary)],
+        };
+        if !changes.is_empty() {
+            entries.push(Entry {
+                commit_id: commit_id.to_string(),
+                timestamp: timestamp.to_string(),
+                changes,
+            });
+        }
+        previous = Some(version);
     }
 
-    task.blocked_by.push(blocker.id.clone());
+    if json {
+        println!("{}", serde_json::to_string(&entries)?);
+    } else {
+        for entry in &entries {
+            println!("{} {}", entry.commit_id, entry.timestamp);
+            for change in &entry.changes {
+                let mut lines = change.lines();
+                println!("    {}", lines.next().unwrap_or_default());
+                for line in lines {
+                    println!("      {line}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_check_add(id: &str, text: String, json: bool) -> Result<()> {
+    let mut task = load_task(id)?;
+    if text.trim().is_empty() || text.contains('\n') {
+        bail!("checklist item must be a single non-empty line");
+    }
+    task.checklist.push(CheckItem { text, done: false });
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("{} blocked by {}", task.id, blocker.id);
+        println!("{} item {} added", task.id, task.checklist.len());
     }
     Ok(())
 }
 
-fn cmd_unblock(id: &str, from: &str, json: bool) -> Result<()> {
+fn cmd_check_rm(id: &str, item: usize, json: bool) -> Result<()> {
     let mut task = load_task(id)?;
-    let blocker = load_task(from)?;
-
-    let before = task.blocked_by.len();
-    task.blocked_by.retain(|b| b != &blocker.id);
-    if task.blocked_by.len() == before {
-        bail!("{} is not blocked by {}", task.id, blocker.id);
+    if item == 0 || item > task.checklist.len() {
+        bail!("{} has no checklist item {}", task.id, item);
     }
-
+    let removed = task.checklist.remove(item - 1);
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("{} unblocked from {}", task.id, blocker.id);
+        println!("{} item {} removed: {}", task.id, item, removed.text);
     }
     Ok(())
 }
 
-fn cmd_note(id: &str, body: &str, author: Option<String>, json: bool) -> Result<()> {
+fn cmd_check(id: &str, item: usize, done: bool, json: bool) -> Result<()> {
     let mut task = load_task(id)?;
-    let author = author
-        .or_else(|| task.agent.clone())
-        .or_else(default_agent)
-        .unwrap_or_else(|| "unknown".into());
-
-    task.notes.push(Note {
-        author,
-        timestamp: Utc::now().to_rfc3339(),
-        body: body.to_string(),
-    });
+    let Some(entry) = item.checked_sub(1).and_then(|i| task.checklist.get_mut(i)) else {
+        bail!("{} has no checklist item {}", task.id, item);
+    };
+    entry.done = done;
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
-        println!("noted on {}", task.id);
+        let (checked, total) = task.checklist_progress();
+        let verb = if done { "checked" } else { "unchecked" };
+        println!("{} item {} {verb} [{checked}/{total}]", task.id, item);
     }
     Ok(())
 }
 
-fn cmd_link(id: &str, target: &str, kind: LinkKind, json: bool) -> Result<()> {
-    let mut task = load_task(id)?;
-    let target_task = load_task(target)?;
+fn cmd_questions(json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    let questions: Vec<(&Task, &Note)> = tasks
+        .iter()
+        .flat_map(|t| t.open_questions().into_iter().map(move |n| (t, n)))
+        .collect();
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct JsonQuestion<'a> {
+            task: &'a str,
+            summary: &'a str,
+            #[serde(flatten)]
+            note: &'a Note,
+        }
+        let rows: Vec<JsonQuestion> = questions
+            .iter()
+            .map(|(t, n)| JsonQuestion {
+                task: &t.id,
+                summary: &t.summary,
+                note: n,
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&rows)?);
+    } else {
+        if questions.is_empty() {
+            println!("no open questions");
+            return Ok(());
+        }
+        let ids = id_prefixes(&tasks);
+        for (t, n) in &questions {
+            let first_line = n.body.lines().next().unwrap_or_default();
+            println!("{} [{}] {first_line}", ids.show(&t.id, 13), n.author);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_link(id: &str, target: &str, kind: LinkKind, close: bool, json: bool) -> Result<()> {
+    // Hold the lock throughout: the effect record describes the other tasks as read here.
+    let _lock = RepoLock::acquire()?;
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    let mut target_task = find_task(target, &tasks)?.clone();
 
+    if task.id == target_task.id {
+        bail!("a task cannot link to itself");
+    }
     if task
         .links
         .iter()
@@ -553,17 +2752,291 @@ fn cmd_link(id: &str, target: &str, kind: LinkKind, json: bool) -> Result<()> {
         target: target_task.id.clone(),
         kind,
     });
+    let mut changed = Vec::new();
+    match kind {
+        LinkKind::Duplicates if close && task.status != Status::Done => {
+            let effect = close_duplicate(&mut task, &mut target_task, &tasks)?;
+            task.effects.push(effect);
+            changed.push(target_task.clone());
+        }
+        // Superseding a task that is already done changes nothing else.
+        LinkKind::Supersedes if target_task.status != Status::Done => {
+            let (effect, dependents) = supersede(&mut task, &mut target_task, &tasks)?;
+            task.effects.push(effect);
+            changed.push(target_task.clone());
+            changed.extend(dependents);
+        }
+        _ => {}
+    }
+    // Save the effect record last, so it never describes changes not made.
+    for other in &changed {
+        save_task(other)?;
+    }
     save_task(&task)?;
 
     if json {
         println!("{}", serde_json::to_string(&task)?);
     } else {
         println!("{} -> {} ({})", task.id, target_task.id, kind);
+        if let Some(effect) = task.effects.iter().find(|e| e.target == target_task.id) {
+            match kind {
+                LinkKind::Duplicates => println!(
+                    "{} closed; notes copied and {} blocker(s) moved to {}",
+                    task.id,
+                    effect.moved.len(),
+                    target_task.id
+                ),
+                _ => println!(
+                    "{} closed; {} dependent(s) now wait on {}",
+                    target_task.id,
+                    effect.redirected.len() + effect.dropped.len(),
+                    task.id
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Close `dup` as a duplicate of `canonical`: copy its live notes over
+/// (tagged with where they came from) and move its blockers across.
+fn close_duplicate(dup: &mut Task, canonical: &mut Task, tasks: &[Task]) -> Result<LinkEffect> {
+    let graph = DepGraph::from_tasks(tasks);
+    let mut moved = Vec::new();
+    for blocker in &dup.blocked_by {
+        if *blocker == canonical.id || canonical.blocked_by.contains(blocker) {
+            continue;
+        }
+        if let Some(cycle) = graph.cycle_if_blocked(&canonical.id, blocker) {
+            bail!(
+                "moving blocker {blocker} onto {} would create a cycle: {}",
+                canonical.id,
+                cycle.join(" -> ")
+            );
+        }
+        moved.push(blocker.clone());
+    }
+    canonical.blocked_by.extend(moved.iter().cloned());
+
+    let mut new_ids = HashMap::new();
+    for note in dup.notes.iter().filter(|n| n.deleted_at.is_none()) {
+        let mut copy = note.clone();
+        copy.id = canonical.next_note_id();
+        copy.reply_to = note.reply_to.and_then(|p| new_ids.get(&p).copied());
+        copy.origin = Some(dup.id.clone());
+        new_ids.insert(note.id, copy.id);
+        canonical.notes.push(copy);
+    }
+
+    let effect = LinkEffect {
+        target: canonical.id.clone(),
+        kind: LinkKind::Duplicates,
+        status: dup.status,
+        blockers: std::mem::take(&mut dup.blocked_by),
+        moved,
+        redirected: vec![],
+        dropped: vec![],
+    };
+    dup.status = Status::Done;
+    dup.lease_until = None;
+    dup.done_at = Some(Utc::now().to_rfc3339());
+    Ok(effect)
+}
+
+/// Close `old` as superseded by `new` and point everything waiting on `old`
+/// at `new` instead. Returns the dependents that changed (other than `new`).
+fn supersede(new: &mut Task, old: &mut Task, tasks: &[Task]) -> Result<(LinkEffect, Vec<Task>)> {
+    let graph = DepGraph::from_tasks(tasks);
+    let mut redirected = Vec::new();
+    let mut dropped = Vec::new();
+    let mut dependents = Vec::new();
+    for t in tasks.iter().filter(|t| t.blocked_by.contains(&old.id)) {
+        if t.id == new.id {
+            new.blocked_by.retain(|b| b != &old.id);
+            dropped.push(t.id.clone());
+            continue;
+        }
+        let mut dependent = t.clone();
+        dependent.blocked_by.retain(|b| b != &old.id);
+        if t.blocked_by.contains(&new.id) {
+            dropped.push(t.id.clone());
+        } else {
+            if let Some(cycle) = graph.cycle_if_blocked(&t.id, &new.id) {
+                bail!(
+                    "redirecting {} to {} would create a cycle: {}",
+                    t.id,
+                    new.id,
+                    cycle.join(" -> ")
+                );
+            }
+            dependent.blocked_by.push(new.id.clone());
+            redirected.push(t.id.clone());
+        }
+        dependents.push(dependent);
+    }
+
+    let effect = LinkEffect {
+        target: old.id.clone(),
+        kind: LinkKind::Supersedes,
+        status: old.status,
+        blockers: vec![],
+        moved: vec![],
+        redirected,
+        dropped,
+    };
+    old.status = Status::Done;
+    old.lease_until = None;
+    old.done_at = Some(Utc::now().to_rfc3339());
+    Ok((effect, dependents))
+}
+
+fn cmd_unlink(id: &str, target: &str, kind: Option<LinkKind>, json: bool) -> Result<()> {
+    let _lock = RepoLock::acquire()?;
+    let tasks = load_all_tasks()?;
+    let mut task = find_task(id, &tasks)?.clone();
+    let target_id = resolve_ref(target, &tasks, task.links.iter().map(|l| &l.target))?;
+    let matches = |t: &str, k: LinkKind| t == target_id && kind.is_none_or(|kind| kind == k);
+
+    let before = task.links.len();
+    task.links.retain(|l| !matches(&l.target, l.kind));
+    if task.links.len() == before {
+        bail!("{} is not linked to {}", task.id, target_id);
+    }
+    let (undo, keep): (Vec<LinkEffect>, Vec<LinkEffect>) = std::mem::take(&mut task.effects)
+        .into_iter()
+        .partition(|e| matches(&e.target, e.kind));
+    task.effects = keep;
+
+    let mut changed: Vec<Task> = Vec::new();
+    for effect in &undo {
+        undo_effect(&mut task, effect, &tasks, &mut changed)?;
+    }
+    // Drop the effect record only once the tasks it describes are restored.
+    for other in &changed {
+        save_task(other)?;
+    }
+    save_task(&task)?;
+
+    if json {
+        println!("{}", serde_json::to_string(&task)?);
+    } else {
+        println!("{} unlinked from {}", task.id, target_id);
+        for effect in &undo {
+            println!("undid {} effects on {}", effect.kind, effect.target);
+        }
+    }
+    Ok(())
+}
+
+/// Reverse what a duplicates/supersedes link did. Tasks other than `task`
+/// that need saving are collected in `changed`.
+fn undo_effect(
+    task: &mut Task,
+    effect: &LinkEffect,
+    tasks: &[Task],
+    changed: &mut Vec<Task>,
+) -> Result<()> {
+    let reopen = |t: &mut Task, status: Status| {
+        t.status = status;
+        if status != Status::Done {
+            t.done_at = None;
+        }
+    };
+
+    match effect.kind {
+        LinkKind::Duplicates => {
+            let canonical = pending(changed, tasks, &effect.target)?;
+            canonical.blocked_by.retain(|b| !effect.moved.contains(b));
+            canonical
+                .notes
+                .retain(|n| n.origin.as_deref() != Some(task.id.as_str()));
+            for blocker in &effect.blockers {
+                if !task.blocked_by.contains(blocker) {
+                    task.blocked_by.push(blocker.clone());
+                }
+            }
+            reopen(task, effect.status);
+        }
+        LinkKind::Supersedes => {
+            reopen(pending(changed, tasks, &effect.target)?, effect.status);
+            let new_id = task.id.clone();
+            for dep_id in effect.redirected.iter().chain(&effect.dropped) {
+                let dependent = if *dep_id == new_id {
+                    &mut *task
+                } else {
+                    pending(changed, tasks, dep_id)?
+                };
+                if effect.redirected.contains(dep_id) {
+                    dependent.blocked_by.retain(|b| *b != new_id);
+                }
+                if !dependent.blocked_by.contains(&effect.target) {
+                    dependent.blocked_by.push(effect.target.clone());
+                }
+            }
+        }
+        LinkKind::RelatesTo => {}
+    }
+    Ok(())
+}
+
+/// The copy of task `id` in `changed`, taken from `tasks` on first use.
+fn pending<'c>(changed: &'c mut Vec<Task>, tasks: &[Task], id: &str) -> Result<&'c mut Task> {
+    let pos = match changed.iter().position(|t| t.id == id) {
+        Some(pos) => pos,
+        None => {
+            let t = tasks
+                .iter()
+                .find(|t| t.id == id)
+                .with_context(|| format!("cannot undo link: {id} is no longer on the board"))?;
+            changed.push(t.clone());
+            changed.len() - 1
+        }
+    };
+    Ok(&mut changed[pos])
+}
+
+fn cmd_doctor(check_cycles: bool, check_dangling: bool, json: bool) -> Result<()> {
+    let tasks = load_all_tasks()?;
+    // With no check selected, run them all.
+    let all = !(check_cycles || check_dangling);
+    let cycles = if all || check_cycles {
+        DepGraph::from_tasks(&tasks).cycles()
+    } else {
+        vec![]
+    };
+    let mut dangling: Vec<(String, Vec<String>)> = if all || check_dangling {
+        blocker_status(&tasks)?.dangling.into_iter().collect()
+    } else {
+        vec![]
+    };
+    dangling.sort();
+
+    if json {
+        let dangling: Vec<_> = dangling
+            .iter()
+            .map(|(id, missing)| serde_json::json!({ "id": id, "missing": missing }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string(&serde_json::json!({ "cycles": cycles, "dangling": dangling }))?
+        );
+    } else if cycles.is_empty() && dangling.is_empty() {
+        println!("no problems found");
+    } else {
+        for cycle in &cycles {
+            println!("cycle: {}", cycle.join(" -> "));
+        }
+        for (id, missing) in &dangling {
+            println!("dangling: {id} blocked by missing {}", missing.join(", "));
+        }
+    }
+    if !cycles.is_empty() || !dangling.is_empty() {
+        return Err(ExitStatus(1).into());
     }
     Ok(())
 }
 
-fn cmd_decay(before: &str, json: bool) -> Result<()> {
+fn cmd_decay(before: &str, prune: bool, json: bool) -> Result<()> {
     let days: i64 = before
         .strip_suffix('d')
         .and_then(|n| n.parse().ok())
@@ -585,24 +3058,64 @@ fn cmd_decay(before: &str, json: bool) -> Result<()> {
         }
     }
 
-    if abandoned.is_empty() {
+    if abandoned.is_empty() && !prune {
         if json {
-            println!(r#"{{"decayed":0}}"#);
+            println!(r#"{{"decayed":0,"pruned":0}}"#);
         } else {
             println!("nothing to decay");
         }
         return Ok(());
     }
 
+    // Record decayed IDs before abandoning, so blockers on them stay satisfied.
+    let _lock = RepoLock::acquire()?;
+    let mut archive = Jj::archive_description()?
+        .map(|d| Archive::from_description(&d))
+        .unwrap_or_default();
+    if !abandoned.is_empty() {
+        for task in &abandoned {
+            if !archive.decayed.contains(&task.id) {
+                archive.decayed.push(task.id.clone());
+            }
+        }
+        Jj::write_archive(&archive.to_description())?;
+    }
+
     let count = abandoned.len();
     for task in &abandoned {
         Jj::abandon(&task.id)?;
     }
 
+    // Dependents of anything decayed, now or in an earlier run.
+    let decayed: HashSet<&str> = archive.decayed.iter().map(String::as_str).collect();
+    let mut dependents: Vec<Task> = tasks
+        .iter()
+        .filter(|t| !decayed.contains(t.id.as_str()))
+        .filter(|t| t.blocked_by.iter().any(|b| decayed.contains(b.as_str())))
+        .cloned()
+        .collect();
+    if prune {
+        for task in &mut dependents {
+            task.blocked_by.retain(|b| !decayed.contains(b.as_str()));
+            save_task(task)?;
+        }
+    }
+    let pruned = if prune { dependents.len() } else { 0 };
+
     if json {
-        println!(r#"{{"decayed":{count}}}"#);
+        println!(r#"{{"decayed":{count},"pruned":{pruned}}}"#);
     } else {
-        println!("decayed {} tasks (jj abandon)", count);
+        if count > 0 {
+            println!("decayed {} tasks (jj abandon)", count);
+        }
+        if prune {
+            println!("pruned decayed blockers from {pruned} tasks");
+        } else if !dependents.is_empty() {
+            println!(
+                "{} tasks still list decayed blockers (treated as done); `jjt decay --prune` drops them",
+                dependents.len()
+            );
+        }
     }
     Ok(())
 }
@@ -0,0 +1,491 @@
+//! Task filter expressions for `jjt list`, e.g.
+//! `status:open & priority<=2 & (label:api | agent:claude) & !blocked`.
+//!
+//! `&` binds tighter than `|`; `!` negates; parentheses group. `@name` expands
+//! the saved query `jjt.queries.name`. Values with spaces or operator
+//! characters can be double-quoted: `agent:"Jane Doe"`.
+
+use crate::identity::Identity;
+use crate::task::{Status, Task};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Pred(Pred),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    fn holds<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Cmp::Eq => a == b,
+            Cmp::Ne => a != b,
+            Cmp::Lt => a < b,
+            Cmp::Le => a <= b,
+            Cmp::Gt => a > b,
+            Cmp::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pred {
+    All,
+    /// Claimable and not blocked.
+    Ready,
+    Blocked,
+    /// Claimed by or assigned to the current agent.
+    Mine,
+    Status(Status),
+    Priority(Cmp, u8),
+    /// Tasks without an estimate never match.
+    Estimate(Cmp, u32),
+    Label(String),
+    /// Person predicates take a name, email or "me".
+    Agent(String),
+    Assignee(String),
+    Reviewer(String),
+    Watcher(String),
+    /// Task ID prefix.
+    Id(String),
+    /// Linked change ID prefix.
+    Change(String),
+    /// Case-insensitive substring of the summary.
+    Summary(String),
+    /// Changed within this age.
+    Updated(Duration),
+    /// Created within this age.
+    Created(Duration),
+}
+
+/// What a query is evaluated against besides the task itself.
+pub struct Scope<'a> {
+    pub now: DateTime<Utc>,
+    pub me: Option<Identity>,
+    pub blocked: &'a HashSet<String>,
+}
+
+impl Expr {
+    /// Parse a query. `saved` looks up `@name` references.
+    pub fn parse(input: &str, saved: &dyn Fn(&str) -> Option<String>) -> Result<Expr> {
+        let mut parser = Parser {
+            tokens: lex(input)?,
+            pos: 0,
+            saved,
+            expanding: Vec::new(),
+        };
+        parser.parse_all(input)
+    }
+
+    /// Both sides must hold.
+    pub fn and(self, other: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, task: &Task, scope: &Scope) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(task, scope) && b.matches(task, scope),
+            Expr::Or(a, b) => a.matches(task, scope) || b.matches(task, scope),
+            Expr::Not(e) => !e.matches(task, scope),
+            Expr::Pred(p) => p.matches(task, scope),
+        }
+    }
+}
+
+impl Pred {
+    fn matches(&self, task: &Task, scope: &Scope) -> bool {
+        let person = |name: &str, stored: &[String]| {
+            let who = match name {
+                "me" => match scope.me {
+                    Some(ref me) => me.clone(),
+                    None => return false,
+                },
+                _ => Identity::parse(name),
+            };
+            stored.iter().any(|s| who.matches(s))
+        };
+        let within = |ts: &Option<String>, age: &Duration| {
+            ts.as_deref()
+                .and_then(|ts| ts.parse::<DateTime<Utc>>().ok())
+                .is_some_and(|ts| ts >= scope.now - *age)
+        };
+        match self {
+            Pred::All => true,
+            Pred::Ready => task.is_claimable(scope.now) && !scope.blocked.contains(&task.id),
+            Pred::Blocked => scope.blocked.contains(&task.id),
+            Pred::Mine => person("me", task.agent.as_slice()) || person("me", &task.assignees),
+            Pred::Status(status) => task.status == *status,
+            Pred::Priority(cmp, n) => cmp.holds(task.priority, *n),
+            Pred::Estimate(cmp, n) => task.estimate.is_some_and(|e| cmp.holds(e, *n)),
+            Pred::Label(label) => task.labels.contains(label),
+            Pred::Agent(name) => person(name, task.agent.as_slice()),
+            Pred::Assignee(name) => person(name, &task.assignees),
+            Pred::Reviewer(name) => person(name, &task.reviewers),
+            Pred::Watcher(name) => person(name, &task.watchers),
+            Pred::Id(prefix) => task.id.starts_with(prefix.as_str()),
+            Pred::Change(prefix) => task
+                .change
+                .as_deref()
+                .is_some_and(|c| c.starts_with(prefix.as_str())),
+            Pred::Summary(text) => task.summary.to_lowercase().contains(&text.to_lowercase()),
+            Pred::Updated(age) => within(&task.updated_at, age),
+            Pred::Created(age) => within(&task.created_at, age),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// A predicate or `@name`, with quotes already removed.
+    Word(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "'&'"),
+            Token::Or => write!(f, "'|'"),
+            Token::Not => write!(f, "'!'"),
+            Token::Word(w) => write!(f, "{w:?}"),
+        }
+    }
+}
+
+fn lex(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let single = match c {
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            '&' => Some(Token::And),
+            '|' => Some(Token::Or),
+            '!' => Some(Token::Not),
+            _ => None,
+        };
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if let Some(token) = single {
+            chars.next();
+            tokens.push(token);
+            continue;
+        }
+        // A word runs to whitespace or a structural character; `!` inside a
+        // word is part of `!=`, and quoted sections may contain anything.
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || matches!(c, '(' | ')' | '&' | '|') {
+                break;
+            }
+            chars.next();
+            if c != '"' {
+                word.push(c);
+                continue;
+            }
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => word.extend(chars.next()),
+                    Some(c) => word.push(c),
+                    None => bail!("unterminated quote in query"),
+                }
+            }
+        }
+        tokens.push(Token::Word(word));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'s> {
+    tokens: Vec<Token>,
+    pos: usize,
+    saved: &'s dyn Fn(&str) -> Option<String>,
+    /// Saved queries being expanded, to catch self-reference.
+    expanding: Vec<String>,
+}
+
+impl Parser<'_> {
+    fn parse_all(&mut self, input: &str) -> Result<Expr> {
+        if self.tokens.is_empty() {
+            bail!("empty query");
+        }
+        let expr = self.parse_or()?;
+        if let Some(token) = self.tokens.get(self.pos) {
+            bail!("unexpected {token} in query {input:?}");
+        }
+        Ok(expr)
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.next_if(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_not()?;
+        while self.next_if(&Token::And) {
+            expr = expr.and(self.parse_not()?);
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.next_if(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .context("query ends unexpectedly")?;
+        self.pos += 1;
+        match token {
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                if !self.next_if(&Token::RParen) {
+                    bail!("missing ')' in query");
+                }
+                Ok(expr)
+            }
+            Token::Word(word) => match word.strip_prefix('@') {
+                Some(name) => self.expand(name),
+                None => Ok(Expr::Pred(parse_pred(&word)?)),
+            },
+            other => bail!("unexpected {other} in query"),
+        }
+    }
+
+    fn expand(&mut self, name: &str) -> Result<Expr> {
+        if self.expanding.iter().any(|n| n == name) {
+            bail!("saved query @{name} refers to itself");
+        }
+        let text = (self.saved)(name)
+            .with_context(|| format!("no saved query @{name} (set jjt.queries.{name})"))?;
+        let mut inner = Parser {
+            tokens: lex(&text)?,
+            pos: 0,
+            saved: self.saved,
+            expanding: self.expanding.clone(),
+        };
+        inner.expanding.push(name.to_string());
+        inner
+            .parse_all(&text)
+            .with_context(|| format!("in saved query @{name}"))
+    }
+}
+
+/// Parse `key<op>value` or a bare keyword.
+fn parse_pred(word: &str) -> Result<Pred> {
+    let key_len = word
+        .find(|c: char| !(c.is_ascii_lowercase() || c == '_'))
+        .unwrap_or(word.len());
+    let (key, rest) = word.split_at(key_len);
+    if rest.is_empty() {
+        return Ok(match key {
+            "all" => Pred::All,
+            "ready" => Pred::Ready,
+            "blocked" => Pred::Blocked,
+            "mine" => Pred::Mine,
+            _ => bail!("unknown query term {word:?}"),
+        });
+    }
+    let ops = [
+        ("<=", Cmp::Le),
+        (">=", Cmp::Ge),
+        ("!=", Cmp::Ne),
+        ("<", Cmp::Lt),
+        (">", Cmp::Gt),
+        ("=", Cmp::Eq),
+        (":", Cmp::Eq),
+    ];
+    let (cmp, value) = ops
+        .iter()
+        .find_map(|&(op, cmp)| rest.strip_prefix(op).map(|v| (cmp, v)))
+        .with_context(|| format!("expected ':' or a comparison after {key:?} in {word:?}"))?;
+    if value.is_empty() {
+        bail!("missing value in {word:?}");
+    }
+    let eq = || -> Result<&str> {
+        if cmp != Cmp::Eq {
+            bail!("{key} only supports ':' (use ! to negate)");
+        }
+        Ok(value)
+    };
+    let number = || format!("{key} must be a number");
+    Ok(match key {
+        "status" => Pred::Status(eq()?.parse()?),
+        "priority" | "p" => Pred::Priority(cmp, value.parse().with_context(number)?),
+        "estimate" => Pred::Estimate(cmp, value.parse().with_context(number)?),
+        "label" => Pred::Label(eq()?.into()),
+        "agent" => Pred::Agent(eq()?.into()),
+        "assignee" => Pred::Assignee(eq()?.into()),
+        "reviewer" => Pred::Reviewer(eq()?.into()),
+        "watcher" => Pred::Watcher(eq()?.into()),
+        "id" => Pred::Id(eq()?.into()),
+        "change" => Pred::Change(eq()?.into()),
+        "summary" => Pred::Summary(eq()?.into()),
+        "updated" => Pred::Updated(parse_age(eq()?)?),
+        "created" => Pred::Created(parse_age(eq()?)?),
+        _ => bail!("unknown query key {key:?}"),
+    })
+}
+
+/// Parse an age like "30m", "12h", "7d" or "2w".
+pub fn parse_age(age: &str) -> Result<Duration> {
+    let split = age.char_indices().last().map_or(0, |(i, _)| i);
+    let (n, unit) = age.split_at(split);
+    let n: u32 = n
+        .parse()
+        .with_context(|| format!("invalid age {age:?}, expected e.g. 12h or 7d"))?;
+    let n = i64::from(n);
+    Ok(match unit {
+        "m" => Duration::minutes(n),
+        "h" => Duration::hours(n),
+        "d" => Duration::days(n),
+        "w" => Duration::weeks(n),
+        _ => bail!("invalid age {age:?}, expected a m, h, d or w suffix"),
+    })
+}
+
+/// Quote a value for use in a query.
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_saved(_: &str) -> Option<String> {
+        None
+    }
+
+    fn task(id: &str, headers: &str) -> Task {
+        let desc = format!("jjt: Task {id}\n{headers}");
+        Task::from_description(id.into(), &desc).unwrap()
+    }
+
+    #[test]
+    fn parses_precedence_and_negation() {
+        let expr = Expr::parse("status:open & p<=2 | !label:api", &no_saved).unwrap();
+        let expected = Expr::Or(
+            Box::new(Expr::And(
+                Box::new(Expr::Pred(Pred::Status(Status::Open))),
+                Box::new(Expr::Pred(Pred::Priority(Cmp::Le, 2))),
+            )),
+            Box::new(Expr::Not(Box::new(Expr::Pred(Pred::Label("api".into()))))),
+        );
+        assert_eq!(expr, expected);
+        assert_eq!(
+            Expr::parse("priority!=3", &no_saved).unwrap(),
+            Expr::Pred(Pred::Priority(Cmp::Ne, 3))
+        );
+        assert_eq!(
+            Expr::parse(r#"agent:"Jane Doe <jane@example.com>""#, &no_saved).unwrap(),
+            Expr::Pred(Pred::Agent("Jane Doe <jane@example.com>".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_queries() {
+        for bad in [
+            "",
+            "status:open &",
+            "(ready",
+            "ready)",
+            "colour:red",
+            "priority:high",
+            "label<3",
+            "\"open",
+            "updated:5é",
+            "updated:-3d",
+        ] {
+            assert!(Expr::parse(bad, &no_saved).is_err(), "{bad:?} should fail");
+        }
+    }
+
+    #[test]
+    fn expands_saved_queries() {
+        let saved = |name: &str| match name {
+            "hot" => Some("priority<=1 & @api".to_string()),
+            "api" => Some("label:api".to_string()),
+            "loop" => Some("ready & @loop".to_string()),
+            _ => None,
+        };
+        let expr = Expr::parse("@hot & ready", &saved).unwrap();
+        let text = format!("{expr:?}");
+        assert!(text.contains("Label(\"api\")") && text.contains("Ready"));
+        assert!(Expr::parse("@loop", &saved).is_err());
+        assert!(Expr::parse("@missing", &saved).is_err());
+    }
+
+    #[test]
+    fn evaluates_against_tasks() {
+        let blocked = HashSet::from(["b".to_string()]);
+        let scope = Scope {
+            now: Utc::now(),
+            me: Some(Identity::parse("claude <claude@example.com>")),
+            blocked: &blocked,
+        };
+        let tasks = [
+            task("a", "status: open\npriority: 1\nlabels: api\n"),
+            task("b", "status: open\npriority: 1\nlabels: api\n"),
+            task("c", "status: claimed\npriority: 2\nagent: claude\n"),
+            task("d", "status: done\npriority: 3\nassignees: claude\n"),
+        ];
+        let select = |q: &str| -> Vec<&str> {
+            let expr = Expr::parse(q, &no_saved).unwrap();
+            tasks
+                .iter()
+                .filter(|t| expr.matches(t, &scope))
+                .map(|t| t.id.as_str())
+                .collect()
+        };
+        assert_eq!(select("status:open & priority<=2 & !blocked"), vec!["a"]);
+        assert_eq!(select("label:api | agent:claude"), vec!["a", "b", "c"]);
+        assert_eq!(select("mine"), vec!["c", "d"]);
+        assert_eq!(select("mine & !status:done"), vec!["c"]);
+        assert_eq!(select("ready"), vec!["a"]);
+        assert_eq!(select("summary:\"task C\""), vec!["c"]);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::task::Task;
+use serde::Serialize;
+
+/// Summary matches count for more than matches in notes.
+const SUMMARY_WEIGHT: u32 = 5;
+const NOTE_WEIGHT: u32 = 2;
+const AUTHOR_WEIGHT: u32 = 1;
+/// Characters of context kept before and after the first match in a snippet.
+const CONTEXT_BEFORE: usize = 30;
+const CONTEXT_AFTER: usize = 60;
+
+/// A task matching every search term.
+#[derive(Serialize)]
+pub struct Hit<'a> {
+    #[serde(skip)]
+    pub task: &'a Task,
+    pub score: u32,
+    /// Where the best match is: "summary", "note #N" or "author of note #N".
+    pub field: String,
+    /// Text around the match on one line.
+    pub snippet: String,
+    /// Byte ranges of term matches within `snippet`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Case-insensitive search over summaries, note bodies and note authors.
+/// A task matches when every term occurs somewhere; hits are ranked by
+/// weighted match count, then priority.
+pub fn search<'a>(tasks: &'a [Task], terms: &[String]) -> Vec<Hit<'a>> {
+    let terms: Vec<String> = terms.iter().map(|t| t.to_lowercase()).collect();
+    let mut hits: Vec<Hit> = tasks.iter().filter_map(|t| hit(t, &terms)).collect();
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.task.priority.cmp(&b.task.priority))
+    });
+    hits
+}
+
+fn hit<'a>(task: &'a Task, terms: &[String]) -> Option<Hit<'a>> {
+    let mut fields = vec![("summary".to_string(), task.summary.as_str(), SUMMARY_WEIGHT)];
+    for note in task.notes.iter().filter(|n| n.deleted_at.is_none()) {
+        fields.push((
+            format!("note #{}", note.id),
+            note.body.as_str(),
+            NOTE_WEIGHT,
+        ));
+        fields.push((
+            format!("author of note #{}", note.id),
+            note.author.as_str(),
+            AUTHOR_WEIGHT,
+        ));
+    }
+
+    let mut score = 0;
+    let mut best: Option<(u32, &str, &str)> = None;
+    for term in terms {
+        let mut found = false;
+        for (name, text, weight) in &fields {
+            let count = find_all(text, term).len() as u32;
+            if count == 0 {
+                continue;
+            }
+            found = true;
+            score += count * weight;
+            if best.is_none_or(|(w, _, _)| *weight > w) {
+                best = Some((*weight, name, text));
+            }
+        }
+        if !found {
+            return None;
+        }
+    }
+
+    let (_, field, text) = best?;
+    let snippet = snippet(text, terms);
+    let mut ranges: Vec<(usize, usize)> =
+        terms.iter().flat_map(|t| find_all(&snippet, t)).collect();
+    ranges.sort();
+    // Terms can overlap ("log" in "login"); merge so highlights never nest.
+    let mut highlights: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match highlights.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => highlights.push((start, end)),
+        }
+    }
+    Some(Hit {
+        task,
+        score,
+        field: field.to_string(),
+        snippet,
+        highlights,
+    })
+}
+
+/// Byte ranges of case-insensitive, non-overlapping matches of a lowercase `needle`.
+fn find_all(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    if needle.is_empty() {
+        return out;
+    }
+    let mut start = 0;
+    while start < haystack.len() {
+        if let Some(len) = match_at(&haystack[start..], needle) {
+            out.push((start, start + len));
+            start += len;
+        } else {
+            start += haystack[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    out
+}
+
+/// Length in bytes of `s`'s prefix matching `needle`, ignoring case.
+fn match_at(s: &str, needle: &str) -> Option<usize> {
+    let mut chars = s.char_indices();
+    for n in needle.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(s.len(), |(i, _)| i))
+}
+
+/// One line of `text` around its first match, with "…" where it was cut.
+fn snippet(text: &str, terms: &[String]) -> String {
+    let first = terms
+        .iter()
+        .filter_map(|t| find_all(text, t).first().copied())
+        .min()
+        .unwrap_or((0, 0));
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let at = chars.iter().position(|&(i, _)| i >= first.0).unwrap_or(0);
+    let from = at.saturating_sub(CONTEXT_BEFORE);
+    let to = (at + CONTEXT_AFTER).min(chars.len());
+
+    let mut out = String::new();
+    if from > 0 {
+        out.push('…');
+    }
+    out.extend(
+        chars[from..to]
+            .iter()
+            .map(|&(_, c)| if c.is_whitespace() { ' ' } else { c }),
+    );
+    if to < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, desc: &str) -> Task {
+        Task::from_description(id.into(), desc).unwrap()
+    }
+
+    #[test]
+    fn ranks_and_highlights() {
+        let tasks = vec![
+            task(
+                "a",
+                "jjt: Flaky login test\nstatus: open\npriority: 2\n\n--- Jane Doe 2026-02-16T10:00:00+00:00\nSeen on CI.\n",
+            ),
+            task(
+                "b",
+                "jjt: Refactor auth\nstatus: open\npriority: 1\n\n--- claude 2026-02-16T10:00:00+00:00\nThe LOGIN flow needs a test\nfor SSO.\n",
+            ),
+            task("c", "jjt: Docs\nstatus: open\npriority: 1\n"),
+        ];
+
+        let hits = search(&tasks, &["login".into(), "TEST".into()]);
+        let ids: Vec<&str> = hits.iter().map(|h| h.task.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(hits[0].field, "summary");
+        assert_eq!(hits[0].highlights, vec![(6, 11), (12, 16)]);
+        assert_eq!(hits[1].field, "note #1");
+        assert_eq!(hits[1].snippet, "The LOGIN flow needs a test for SSO.");
+        assert_eq!(&hits[1].snippet[4..9], "LOGIN");
+
+        let by_author = search(&tasks, &["jane".into()]);
+        assert_eq!(by_author[0].field, "author of note #1");
+        assert!(search(&tasks, &["login".into(), "docs".into()]).is_empty());
+
+        let overlapping = search(&tasks, &["log".into(), "login".into()]);
+        assert_eq!(overlapping[0].highlights, vec![(6, 11)]);
+    }
+
+    #[test]
+    fn snippets_are_trimmed() {
+        let text = format!("{}needle{}", "x".repeat(100), "y".repeat(100));
+        let s = snippet(&text, &["needle".into()]);
+        assert!(s.starts_with('…') && s.ends_with('…'));
+        assert_eq!(s.chars().count(), CONTEXT_BEFORE + CONTEXT_AFTER + 2);
+        assert_eq!(find_all(&s, "needle").len(), 1);
+    }
+}
@@ -1,9 +1,10 @@
 use anyhow::{bail, Context, Result};
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
     Open,
@@ -33,7 +34,7 @@ impl FromStr for Status {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LinkKind {
     RelatesTo,
@@ -63,31 +64,258 @@ impl FromStr for LinkKind {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Link {
     pub target: String,
     pub kind: LinkKind,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.target, self.kind)
+    }
+}
+
+/// What a `duplicates`/`supersedes` link changed on other tasks, kept on the
+/// linking task so `jjt unlink` can undo it. Stored as
+/// `effect: target/kind status=open blockers=a,b moved=a redirected=c dropped=d`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEffect {
+    pub target: String,
+    pub kind: LinkKind,
+    /// Status of the task that was closed, before it was closed.
+    pub status: Status,
+    /// duplicates: blockers taken off the duplicate.
+    pub blockers: Vec<String>,
+    /// duplicates: the subset of `blockers` newly added to the canonical task.
+    pub moved: Vec<String>,
+    /// supersedes: dependents whose blocker was swapped for the new task.
+    pub redirected: Vec<String>,
+    /// supersedes: dependents that already waited on the new task and just lost the old one.
+    pub dropped: Vec<String>,
+}
+
+impl fmt::Display for LinkEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} status={}", self.target, self.kind, self.status)?;
+        let lists = [
+            ("blockers", &self.blockers),
+            ("moved", &self.moved),
+            ("redirected", &self.redirected),
+            ("dropped", &self.dropped),
+        ];
+        for (key, ids) in lists {
+            if !ids.is_empty() {
+                write!(f, " {key}={}", ids.join(","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LinkEffect {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut tokens = s.split_whitespace();
+        let (target, kind) = tokens
+            .next()
+            .and_then(|t| t.split_once('/'))
+            .context("invalid link effect, expected target/kind")?;
+        let mut effect = LinkEffect {
+            target: target.to_string(),
+            kind: kind.parse()?,
+            status: Status::Open,
+            blockers: vec![],
+            moved: vec![],
+            redirected: vec![],
+            dropped: vec![],
+        };
+        for token in tokens {
+            let ids = |v: &str| v.split(',').map(String::from).collect();
+            match token.split_once('=') {
+                Some(("status", v)) => effect.status = v.parse()?,
+                Some(("blockers", v)) => effect.blockers = ids(v),
+                Some(("moved", v)) => effect.moved = ids(v),
+                Some(("redirected", v)) => effect.redirected = ids(v),
+                Some(("dropped", v)) => effect.dropped = ids(v),
+                _ => {} // ignore unknown attributes for forward compat
+            }
+        }
+        Ok(effect)
+    }
+}
+
+/// When a revset blocker counts as satisfied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevCondition {
+    /// Every commit in the revset is an ancestor of `trunk()`.
+    Landed,
+    /// The revset is non-empty and none of its commits has conflicts.
+    ConflictFree,
+    /// The revset is non-empty (for hand-written conditions).
+    Exists,
+}
+
+impl fmt::Display for RevCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevCondition::Landed => write!(f, "landed"),
+            RevCondition::ConflictFree => write!(f, "conflict_free"),
+            RevCondition::Exists => write!(f, "exists"),
+        }
+    }
+}
+
+impl FromStr for RevCondition {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "landed" => Ok(RevCondition::Landed),
+            "conflict_free" => Ok(RevCondition::ConflictFree),
+            "exists" => Ok(RevCondition::Exists),
+            _ => bail!("unknown revset condition: {s}"),
+        }
+    }
+}
+
+/// A blocker on a jj revset rather than another task, stored as
+/// `rev_blocker: <condition> <revset>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RevBlocker {
+    pub condition: RevCondition,
+    pub revset: String,
+}
+
+impl fmt::Display for RevBlocker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.condition, self.revset)
+    }
+}
+
+impl FromStr for RevBlocker {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (condition, revset) = s
+            .split_once(' ')
+            .context("invalid rev_blocker, expected 'condition revset'")?;
+        Ok(RevBlocker {
+            condition: condition.parse()?,
+            revset: revset.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckItem {
+    pub text: String,
+    pub done: bool,
+}
+
+impl fmt::Display for CheckItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = if self.done { 'x' } else { ' ' };
+        write!(f, "[{mark}] {}", self.text)
+    }
+}
+
+impl FromStr for CheckItem {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (done, text) = if let Some(text) = s.strip_prefix("[ ] ") {
+            (false, text)
+        } else if let Some(text) = s.strip_prefix("[x] ") {
+            (true, text)
+        } else {
+            bail!("invalid checklist item, expected '[ ] text' or '[x] text': {s}");
+        };
+        Ok(CheckItem {
+            text: text.to_string(),
+            done,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteKind {
+    Decision,
+    Question,
+    Answer,
+    Blocker,
+    Handoff,
+    Log,
+}
+
+impl fmt::Display for NoteKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteKind::Decision => write!(f, "decision"),
+            NoteKind::Question => write!(f, "question"),
+            NoteKind::Answer => write!(f, "answer"),
+            NoteKind::Blocker => write!(f, "blocker"),
+            NoteKind::Handoff => write!(f, "handoff"),
+            NoteKind::Log => write!(f, "log"),
+        }
+    }
+}
+
+impl FromStr for NoteKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "decision" => Ok(NoteKind::Decision),
+            "question" => Ok(NoteKind::Question),
+            "answer" => Ok(NoteKind::Answer),
+            "blocker" => Ok(NoteKind::Blocker),
+            "handoff" => Ok(NoteKind::Handoff),
+            "log" => Ok(NoteKind::Log),
+            _ => bail!("unknown note kind: {s}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
+    pub id: u32, // stable per-task note number
     pub author: String,
     pub timestamp: String,
+    pub kind: Option<NoteKind>,
+    pub reply_to: Option<u32>,
+    pub edited_at: Option<String>,
+    pub deleted_at: Option<String>, // tombstone: body is cleared
+    pub origin: Option<String>,     // task the note was copied from
     pub body: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String, // jj change ID
     pub status: Status,
     pub summary: String,
     pub priority: u8,
-    pub agent: Option<String>,
+    pub estimate: Option<u32>, // effort, in whatever unit the board uses
+    pub labels: Vec<String>,
+    pub agent: Option<String>,       // who claimed it
+    pub lease_until: Option<String>, // claim expiry; none means the claim never expires
+    pub assignees: Vec<String>,
+    pub reviewers: Vec<String>,
+    pub watchers: Vec<String>,
     pub change: Option<String>, // linked code change ID
     pub done_at: Option<String>,
+    pub verify: Option<String>,  // shell command gating `jjt done`
     pub blocked_by: Vec<String>, // change IDs of blocking tasks
+    pub rev_blockers: Vec<RevBlocker>,
+    pub after: Vec<String>, // soft dependencies: ordering hints, never gates
     pub links: Vec<Link>,
+    pub effects: Vec<LinkEffect>, // undo records for links that changed other tasks
+    pub checklist: Vec<CheckItem>, // acceptance criteria
     pub notes: Vec<Note>,
+    // Derived from commit metadata, not stored in the description:
+    pub created_by: Option<String>, // commit author
+    pub created_at: Option<String>, // author timestamp
+    pub updated_at: Option<String>, // committer timestamp
+    pub commit_id: Option<String>,
 }
 
 impl Task {
@@ -104,12 +332,24 @@ impl Task {
 
         let mut status = Status::Open;
         let mut priority = 2u8;
+        let mut estimate = None;
+        let mut labels = Vec::new();
         let mut agent = None;
+        let mut lease_until = None;
+        let mut assignees = Vec::new();
+        let mut reviewers = Vec::new();
+        let mut watchers = Vec::new();
         let mut change = None;
         let mut done_at = None;
+        let mut verify = None;
         let mut blocked_by = Vec::new();
+        let mut rev_blockers = Vec::new();
+        let mut after = Vec::new();
         let mut links = Vec::new();
+        let mut effects = Vec::new();
+        let mut checklist = Vec::new();
         let mut notes = Vec::new();
+        let mut note_ids: Vec<Option<u32>> = Vec::new();
 
         // Parse key-value headers
         while let Some(&line) = lines.peek() {
@@ -127,24 +367,21 @@ impl Task {
             match key {
                 "status" => status = value.parse()?,
                 "priority" => priority = value.parse()?,
-                "agent" => {
-                    if !value.is_empty() {
-                        agent = Some(value.to_string());
-                    }
-                }
-                "change" => {
-                    if !value.is_empty() {
-                        change = Some(value.to_string());
-                    }
-                }
-                "done_at" => {
-                    if !value.is_empty() {
-                        done_at = Some(value.to_string());
-                    }
-                }
+                "estimate" if !value.is_empty() => estimate = Some(value.parse()?),
+                "labels" => labels = value.split_whitespace().map(String::from).collect(),
+                "agent" if !value.is_empty() => agent = Some(value.to_string()),
+                "lease_until" if !value.is_empty() => lease_until = Some(value.to_string()),
+                "assignees" => assignees = split_people(value),
+                "reviewers" => reviewers = split_people(value),
+                "watchers" => watchers = split_people(value),
+                "change" if !value.is_empty() => change = Some(value.to_string()),
+                "done_at" if !value.is_empty() => done_at = Some(value.to_string()),
+                "verify" if !value.is_empty() => verify = Some(value.to_string()),
                 "blocked_by" => {
                     blocked_by = value.split_whitespace().map(String::from).collect();
                 }
+                "rev_blocker" => rev_blockers.push(value.parse()?),
+                "after" => after = value.split_whitespace().map(String::from).collect(),
                 "links" => {
                     for part in value.split_whitespace() {
                         let (target, kind) = part
@@ -156,6 +393,8 @@ impl Task {
                         });
                     }
                 }
+                "effect" => effects.push(value.parse()?),
+                "check" => checklist.push(value.parse()?),
                 _ => {} // ignore unknown keys for forward compat
             }
         }
@@ -165,10 +404,33 @@ impl Task {
             if !line.starts_with("--- ") {
                 continue;
             }
-            let header = &line[4..];
-            let (author, timestamp) = header
-                .split_once(' ')
+            // "--- author timestamp [key=value ...]"; the author may contain
+            // spaces ("Jane Doe <jane@example.com>"), so find the timestamp.
+            let tokens: Vec<&str> = line[4..].split_whitespace().collect();
+            let ts_pos = tokens
+                .iter()
+                .position(|t| DateTime::parse_from_rfc3339(t).is_ok())
                 .context("invalid note header, expected 'author timestamp'")?;
+            let author = tokens[..ts_pos].join(" ");
+            let timestamp = tokens[ts_pos];
+            let fields = tokens[ts_pos + 1..].iter();
+            let mut id = None;
+            let mut kind = None;
+            let mut reply_to = None;
+            let mut edited_at = None;
+            let mut deleted_at = None;
+            let mut origin = None;
+            for field in fields {
+                match field.split_once('=') {
+                    Some(("id", value)) => id = Some(value.parse()?),
+                    Some(("kind", value)) => kind = Some(value.parse()?),
+                    Some(("re", value)) => reply_to = Some(value.parse()?),
+                    Some(("edited", value)) => edited_at = Some(value.to_string()),
+                    Some(("deleted", value)) => deleted_at = Some(value.to_string()),
+                    Some(("from", value)) => origin = Some(value.to_string()),
+                    _ => {} // ignore unknown attributes for forward compat
+                }
+            }
 
             let mut body = String::new();
             while let Some(&next_line) = lines.peek() {
@@ -182,65 +444,396 @@ impl Task {
                 lines.next();
             }
 
+            note_ids.push(id);
             notes.push(Note {
-                author: author.to_string(),
+                id: 0,
+                author,
                 timestamp: timestamp.to_string(),
+                kind,
+                reply_to,
+                edited_at,
+                deleted_at,
+                origin,
                 body,
             });
         }
 
+        // Notes written before IDs existed are numbered after the highest known ID.
+        let mut next_id = note_ids.iter().flatten().max().copied().unwrap_or(0) + 1;
+        for (note, id) in notes.iter_mut().zip(note_ids) {
+            note.id = id.unwrap_or_else(|| {
+                next_id += 1;
+                next_id - 1
+            });
+        }
+
         Ok(Task {
             id: change_id,
             status,
             summary,
             priority,
+            estimate,
+            labels,
             agent,
+            lease_until,
+            assignees,
+            reviewers,
+            watchers,
             change,
             done_at,
+            verify,
             blocked_by,
+            rev_blockers,
+            after,
             links,
+            effects,
+            checklist,
             notes,
+            created_by: None,
+            created_at: None,
+            updated_at: None,
+            commit_id: None,
         })
     }
 
     /// Serialize to a jj commit description.
     pub fn to_description(&self) -> String {
+        let mut out = self.to_headers();
+        for note in &self.notes {
+            out.push_str(&format!(
+                "\n--- {} {} id={}",
+                note.author, note.timestamp, note.id
+            ));
+            if let Some(kind) = note.kind {
+                out.push_str(&format!(" kind={kind}"));
+            }
+            if let Some(reply_to) = note.reply_to {
+                out.push_str(&format!(" re={reply_to}"));
+            }
+            if let Some(ref edited_at) = note.edited_at {
+                out.push_str(&format!(" edited={edited_at}"));
+            }
+            if let Some(ref deleted_at) = note.deleted_at {
+                out.push_str(&format!(" deleted={deleted_at}"));
+            }
+            if let Some(ref origin) = note.origin {
+                out.push_str(&format!(" from={origin}"));
+            }
+            out.push('\n');
+            out.push_str(&note.body);
+            if !note.body.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Serialize the summary line and key-value headers, without notes.
+    pub fn to_headers(&self) -> String {
         let mut out = format!("jjt: {}\n", self.summary);
         out.push_str(&format!("status: {}\n", self.status));
         out.push_str(&format!("priority: {}\n", self.priority));
+        if let Some(estimate) = self.estimate {
+            out.push_str(&format!("estimate: {estimate}\n"));
+        }
+        if !self.labels.is_empty() {
+            out.push_str(&format!("labels: {}\n", self.labels.join(" ")));
+        }
         if let Some(ref agent) = self.agent {
             out.push_str(&format!("agent: {agent}\n"));
         }
+        if let Some(ref lease_until) = self.lease_until {
+            out.push_str(&format!("lease_until: {lease_until}\n"));
+        }
+        let people = [
+            ("assignees", &self.assignees),
+            ("reviewers", &self.reviewers),
+            ("watchers", &self.watchers),
+        ];
+        for (key, names) in people {
+            if !names.is_empty() {
+                out.push_str(&format!("{key}: {}\n", names.join(", ")));
+            }
+        }
         if let Some(ref change) = self.change {
             out.push_str(&format!("change: {change}\n"));
         }
         if let Some(ref done_at) = self.done_at {
             out.push_str(&format!("done_at: {done_at}\n"));
         }
+        if let Some(ref verify) = self.verify {
+            out.push_str(&format!("verify: {verify}\n"));
+        }
         if !self.blocked_by.is_empty() {
             out.push_str(&format!("blocked_by: {}\n", self.blocked_by.join(" ")));
         }
+        for blocker in &self.rev_blockers {
+            out.push_str(&format!("rev_blocker: {blocker}\n"));
+        }
+        if !self.after.is_empty() {
+            out.push_str(&format!("after: {}\n", self.after.join(" ")));
+        }
         if !self.links.is_empty() {
-            let links: Vec<String> = self
-                .links
-                .iter()
-                .map(|l| format!("{}/{}", l.target, l.kind))
-                .collect();
+            let links: Vec<String> = self.links.iter().map(|l| l.to_string()).collect();
             out.push_str(&format!("links: {}\n", links.join(" ")));
         }
+        for effect in &self.effects {
+            out.push_str(&format!("effect: {effect}\n"));
+        }
+        for item in &self.checklist {
+            out.push_str(&format!("check: {item}\n"));
+        }
+        out
+    }
 
-        for note in &self.notes {
-            out.push_str(&format!("\n--- {} {}\n", note.author, note.timestamp));
-            out.push_str(&note.body);
-            if !note.body.ends_with('\n') {
-                out.push('\n');
+    /// ID for the next note added to this task.
+    pub fn next_note_id(&self) -> u32 {
+        self.notes.iter().map(|n| n.id).max().unwrap_or(0) + 1
+    }
+
+    /// Append a note timestamped now and return it for further tweaks.
+    pub fn add_note(&mut self, author: String, kind: Option<NoteKind>, body: String) -> &mut Note {
+        let id = self.next_note_id();
+        self.notes.push(Note {
+            id,
+            author,
+            timestamp: Utc::now().to_rfc3339(),
+            kind,
+            reply_to: None,
+            edited_at: None,
+            deleted_at: None,
+            origin: None,
+            body,
+        });
+        self.notes.last_mut().expect("note was just pushed")
+    }
+
+    pub fn note_mut(&mut self, id: u32) -> Option<&mut Note> {
+        self.notes.iter_mut().find(|n| n.id == id)
+    }
+
+    /// Questions not yet answered. An answer replying to a question closes
+    /// that question; an unthreaded answer closes the earliest one still open.
+    pub fn open_questions(&self) -> Vec<&Note> {
+        let mut open: Vec<&Note> = Vec::new();
+        for note in self.notes.iter().filter(|n| n.deleted_at.is_none()) {
+            match note.kind {
+                Some(NoteKind::Question) => open.push(note),
+                Some(NoteKind::Answer) => match note.reply_to {
+                    Some(parent) => open.retain(|q| q.id != parent),
+                    None if !open.is_empty() => {
+                        open.remove(0);
+                    }
+                    None => {}
+                },
+                _ => {}
+            }
+        }
+        open
+    }
+
+    /// Describe what changed between this version of the task and `newer`,
+    /// one line per change. Edited and deleted notes keep their old body.
+    pub fn diff(&self, newer: &Task) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.summary != newer.summary {
+            out.push(format!("summary: {} -> {}", self.summary, newer.summary));
+        }
+        if self.status != newer.status {
+            out.push(format!("status: {} -> {}", self.status, newer.status));
+        }
+        if self.priority != newer.priority {
+            out.push(format!("priority: {} -> {}", self.priority, newer.priority));
+        }
+        if self.estimate != newer.estimate {
+            let show = |v: Option<u32>| v.map_or_else(|| "-".into(), |e| e.to_string());
+            out.push(format!(
+                "estimate: {} -> {}",
+                show(self.estimate),
+                show(newer.estimate)
+            ));
+        }
+        let fields = [
+            ("agent", &self.agent, &newer.agent),
+            ("lease_until", &self.lease_until, &newer.lease_until),
+            ("change", &self.change, &newer.change),
+            ("verify", &self.verify, &newer.verify),
+        ];
+        for (name, old, new) in fields {
+            if old != new {
+                let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".into());
+                out.push(format!("{name}: {} -> {}", show(old), show(new)));
+            }
+        }
+        for b in newer
+            .blocked_by
+            .iter()
+            .filter(|b| !self.blocked_by.contains(b))
+        {
+            out.push(format!("blocked by {b}"));
+        }
+        for b in self
+            .blocked_by
+            .iter()
+            .filter(|b| !newer.blocked_by.contains(b))
+        {
+            out.push(format!("unblocked from {b}"));
+        }
+        for b in newer
+            .rev_blockers
+            .iter()
+            .filter(|b| !self.rev_blockers.contains(b))
+        {
+            out.push(format!("blocked until {b}"));
+        }
+        for b in self
+            .rev_blockers
+            .iter()
+            .filter(|b| !newer.rev_blockers.contains(b))
+        {
+            out.push(format!("no longer blocked until {b}"));
+        }
+        for a in newer.after.iter().filter(|a| !self.after.contains(a)) {
+            out.push(format!("after {a}"));
+        }
+        for a in self.after.iter().filter(|a| !newer.after.contains(a)) {
+            out.push(format!("no longer after {a}"));
+        }
+        let old_links: Vec<String> = self.links.iter().map(|l| l.to_string()).collect();
+        let new_links: Vec<String> = newer.links.iter().map(|l| l.to_string()).collect();
+        for l in new_links.iter().filter(|l| !old_links.contains(l)) {
+            out.push(format!("linked {l}"));
+        }
+        for l in old_links.iter().filter(|l| !new_links.contains(l)) {
+            out.push(format!("unlinked {l}"));
+        }
+        let old_checks: Vec<String> = self.checklist.iter().map(|c| c.to_string()).collect();
+        let new_checks: Vec<String> = newer.checklist.iter().map(|c| c.to_string()).collect();
+        for c in new_checks.iter().filter(|c| !old_checks.contains(c)) {
+            out.push(format!("check {c}"));
+        }
+        for c in old_checks.iter().filter(|c| !new_checks.contains(c)) {
+            out.push(format!("check removed {c}"));
+        }
+        for note in &newer.notes {
+            let Some(old) = self.notes.iter().find(|n| n.id == note.id) else {
+                let kind = note.kind.map(|k| format!(" [{k}]")).unwrap_or_default();
+                out.push(format!(
+                    "note #{} by {}{kind}: {}",
+                    note.id, note.author, note.body
+                ));
+                continue;
+            };
+            if note.deleted_at.is_some() && old.deleted_at.is_none() {
+                out.push(format!("note #{} deleted (was: {})", note.id, old.body));
+            } else if note.body != old.body {
+                out.push(format!(
+                    "note #{} edited: {} -> {}",
+                    note.id, old.body, note.body
+                ));
+            }
+        }
+        out
+    }
+
+    /// Whether this is a claim whose lease ran out before `now`.
+    pub fn claim_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status == Status::Claimed
+            && self
+                .lease_until
+                .as_deref()
+                .and_then(|l| l.parse::<DateTime<Utc>>().ok())
+                .is_some_and(|until| until < now)
+    }
+
+    /// Open, or claimed with an expired lease: free for anyone to claim.
+    pub fn is_claimable(&self, now: DateTime<Utc>) -> bool {
+        self.status == Status::Open || self.claim_expired(now)
+    }
+
+    /// Number of checked and total checklist items.
+    pub fn checklist_progress(&self) -> (usize, usize) {
+        let checked = self.checklist.iter().filter(|c| c.done).count();
+        (checked, self.checklist.len())
+    }
+}
+
+/// Reverse edges onto a task: other tasks' `blocked_by` and links that point at it.
+#[derive(Debug, Default, Serialize)]
+pub struct Backlinks {
+    pub blocks: Vec<String>,
+    pub superseded_by: Vec<String>,
+    pub duplicated_by: Vec<String>,
+    pub related_from: Vec<String>,
+}
+
+impl Backlinks {
+    pub fn of(id: &str, tasks: &[Task]) -> Backlinks {
+        let mut out = Backlinks::default();
+        for t in tasks.iter().filter(|t| t.id != id) {
+            if t.blocked_by.iter().any(|b| b == id) {
+                out.blocks.push(t.id.clone());
+            }
+            for link in t.links.iter().filter(|l| l.target == id) {
+                let list = match link.kind {
+                    LinkKind::Supersedes => &mut out.superseded_by,
+                    LinkKind::Duplicates => &mut out.duplicated_by,
+                    LinkKind::RelatesTo => &mut out.related_from,
+                };
+                list.push(t.id.clone());
             }
         }
+        out
+    }
+
+    /// (header name, task IDs) pairs, in display order.
+    pub fn entries(&self) -> [(&'static str, &[String]); 4] {
+        [
+            ("blocks", &self.blocks),
+            ("superseded_by", &self.superseded_by),
+            ("duplicated_by", &self.duplicated_by),
+            ("related_from", &self.related_from),
+        ]
+    }
+}
+
+/// Record of decayed (abandoned) done tasks, kept as the description of the
+/// `jjt-archive` commit so blockers on them still count as satisfied.
+#[derive(Debug, Clone, Default)]
+pub struct Archive {
+    pub decayed: Vec<String>,
+}
 
+impl Archive {
+    pub fn from_description(description: &str) -> Archive {
+        let decayed = description
+            .lines()
+            .filter_map(|l| l.strip_prefix("decayed: "))
+            .map(|id| id.trim().to_string())
+            .collect();
+        Archive { decayed }
+    }
+
+    pub fn to_description(&self) -> String {
+        let mut out = String::from("jjt archive\n\n");
+        for id in &self.decayed {
+            out.push_str(&format!("decayed: {id}\n"));
+        }
         out
     }
 }
 
+/// Split a comma-separated list of names. Names may contain spaces
+/// (e.g. "Jane Doe <jane@example.com>"), so whitespace is not a separator.
+fn split_people(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,16 +844,25 @@ mod tests {
 jjt: Refactor auth module
 status: open
 priority: 1
+estimate: 3
+labels: auth backend
 agent: claude
+assignees: claude, Jane Doe <jane@example.com>
+reviewers: pehle
 change: zxkpmory
+verify: cargo test -p auth
 blocked_by: abc123 def456
+rev_blocker: landed zxkpmory | trunk()
+after: mnopqr
 links: ghi789/relates_to xyz000/supersedes
+check: [x] tests pass
+check: [ ] docs updated
 
 --- claude 2026-02-16T10:05:00+00:00
 Auth module has 3 providers,
 need to handle each separately.
 
---- pehle 2026-02-16T10:12:00+00:00
+--- Christian Pehle <pehle@example.com> 2026-02-16T10:12:00+00:00 kind=decision
 Started with OAuth provider.
 ";
 
@@ -269,20 +871,109 @@ Started with OAuth provider.
         assert_eq!(task.status, Status::Open);
         assert_eq!(task.summary, "Refactor auth module");
         assert_eq!(task.priority, 1);
+        assert_eq!(task.estimate, Some(3));
+        assert_eq!(task.labels, vec!["auth", "backend"]);
         assert_eq!(task.agent.as_deref(), Some("claude"));
+        assert_eq!(
+            task.assignees,
+            vec!["claude", "Jane Doe <jane@example.com>"]
+        );
+        assert_eq!(task.reviewers, vec!["pehle"]);
+        assert!(task.watchers.is_empty());
         assert_eq!(task.change.as_deref(), Some("zxkpmory"));
+        assert_eq!(task.verify.as_deref(), Some("cargo test -p auth"));
         assert_eq!(task.blocked_by, vec!["abc123", "def456"]);
+        assert_eq!(
+            task.rev_blockers,
+            vec![RevBlocker {
+                condition: RevCondition::Landed,
+                revset: "zxkpmory | trunk()".into(),
+            }]
+        );
+        assert_eq!(task.after, vec!["mnopqr"]);
         assert_eq!(task.links.len(), 2);
+        assert_eq!(task.checklist.len(), 2);
+        assert!(task.checklist[0].done);
+        assert_eq!(task.checklist[1].text, "docs updated");
+        assert_eq!(task.checklist_progress(), (1, 2));
         assert_eq!(task.notes.len(), 2);
         assert_eq!(task.notes[0].author, "claude");
         assert!(task.notes[0].body.contains("3 providers"));
+        assert_eq!(task.notes[0].kind, None);
+        assert_eq!(task.notes[0].id, 1);
+        assert_eq!(task.notes[1].id, 2);
+        assert_eq!(task.notes[1].kind, Some(NoteKind::Decision));
+        assert_eq!(task.notes[1].author, "Christian Pehle <pehle@example.com>");
 
         let serialized = task.to_description();
         let task2 = Task::from_description("vruxwmqv".into(), &serialized).unwrap();
         assert_eq!(task2.summary, task.summary);
         assert_eq!(task2.status, task.status);
+        assert_eq!(task2.verify, task.verify);
+        assert_eq!(task2.assignees, task.assignees);
+        assert_eq!(task2.labels, task.labels);
         assert_eq!(task2.blocked_by, task.blocked_by);
+        assert_eq!(task2.checklist_progress(), task.checklist_progress());
         assert_eq!(task2.notes.len(), task.notes.len());
+        assert_eq!(task2.notes[1].kind, Some(NoteKind::Decision));
+        assert_eq!(task2.notes[1].id, 2);
+    }
+
+    #[test]
+    fn note_threads_and_tombstones() {
+        let desc = "\
+jjt: Pick a storage backend
+status: open
+priority: 2
+
+--- claude 2026-02-16T10:00:00+00:00 id=1 kind=question
+Postgres or SQLite?
+
+--- claude 2026-02-16T10:01:00+00:00 id=4 kind=question deleted=2026-02-16T10:02:00+00:00
+
+--- pehle 2026-02-16T10:05:00+00:00 id=5 kind=answer re=1 edited=2026-02-16T10:06:00+00:00
+SQLite for now.
+";
+        let task = Task::from_description("abc".into(), desc).unwrap();
+        assert_eq!(task.notes[1].id, 4);
+        assert!(task.notes[1].deleted_at.is_some());
+        assert!(task.notes[1].body.is_empty());
+        assert_eq!(task.notes[2].reply_to, Some(1));
+        assert!(task.notes[2].edited_at.is_some());
+        assert!(task.open_questions().is_empty());
+        assert_eq!(task.next_note_id(), 6);
+
+        let task2 = Task::from_description("abc".into(), &task.to_description()).unwrap();
+        assert_eq!(task2.notes[2].reply_to, Some(1));
+        assert!(task2.notes[1].deleted_at.is_some());
+
+        let mut edited = task2.clone();
+        edited.note_mut(5).unwrap().body = "Postgres after all.".into();
+        let changes = task2.diff(&edited);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].contains("SQLite for now."));
+    }
+
+    #[test]
+    fn open_questions() {
+        let desc = "\
+jjt: Pick a storage backend
+status: open
+priority: 2
+
+--- claude 2026-02-16T10:00:00+00:00 kind=question
+Postgres or SQLite?
+
+--- claude 2026-02-16T10:01:00+00:00 kind=question
+Do we need migrations?
+
+--- pehle 2026-02-16T10:05:00+00:00 kind=answer
+SQLite for now.
+";
+        let task = Task::from_description("abc".into(), desc).unwrap();
+        let open = task.open_questions();
+        assert_eq!(open.len(), 1);
+        assert!(open[0].body.contains("migrations"));
     }
 
     #[test]
@@ -302,4 +993,85 @@ Started with OAuth provider.
         assert_eq!(task.status, Status::Done);
         assert!(task.done_at.is_some());
     }
+
+    #[test]
+    fn note_with_empty_author() {
+        let mut task = Task::from_description("abc".into(), "jjt: x\nstatus: open\n").unwrap();
+        task.add_note(String::new(), Some(NoteKind::Log), "hi".into());
+        let task2 = Task::from_description("abc".into(), &task.to_description()).unwrap();
+        assert_eq!(task2.notes[0].author, "");
+        assert_eq!(task2.notes[0].body, "hi");
+    }
+
+    #[test]
+    fn link_effects_and_copied_notes() {
+        let desc = "\
+jjt: Fix login
+status: open
+priority: 2
+links: aaa/duplicates
+effect: aaa/duplicates status=claimed blockers=bbb,ccc moved=ccc
+
+--- claude 2026-02-16T10:05:00+00:00 id=4 from=ddd
+Copied over.
+";
+        let task = Task::from_description("eee".into(), desc).unwrap();
+        let effect = &task.effects[0];
+        assert_eq!(effect.target, "aaa");
+        assert_eq!(effect.kind, LinkKind::Duplicates);
+        assert_eq!(effect.status, Status::Claimed);
+        assert_eq!(effect.blockers, vec!["bbb", "ccc"]);
+        assert_eq!(effect.moved, vec!["ccc"]);
+        assert!(effect.redirected.is_empty());
+        assert_eq!(task.notes[0].origin.as_deref(), Some("ddd"));
+        assert_eq!(task.to_description(), desc);
+    }
+
+    #[test]
+    fn backlinks() {
+        let parse = |id: &str, headers: &str| {
+            let desc = format!("jjt: {id}\nstatus: open\npriority: 2\n{headers}");
+            Task::from_description(id.into(), &desc).unwrap()
+        };
+        let tasks = vec![
+            parse("b", ""),
+            parse("a", "blocked_by: b\n"),
+            parse("c", "links: b/supersedes\n"),
+            parse("d", "links: b/duplicates b/relates_to\n"),
+        ];
+        let back = Backlinks::of("b", &tasks);
+        assert_eq!(back.blocks, vec!["a"]);
+        assert_eq!(back.superseded_by, vec!["c"]);
+        assert_eq!(back.duplicated_by, vec!["d"]);
+        assert_eq!(back.related_from, vec!["d"]);
+        assert!(Backlinks::of("a", &tasks)
+            .entries()
+            .iter()
+            .all(|(_, ids)| ids.is_empty()));
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let mut archive = Archive::from_description("jjt archive\n");
+        assert!(archive.decayed.is_empty());
+        archive.decayed.push("vruxwmqvtpmx".into());
+        archive.decayed.push("zxkpmoryabcd".into());
+        let archive2 = Archive::from_description(&archive.to_description());
+        assert_eq!(archive2.decayed, archive.decayed);
+    }
+
+    #[test]
+    fn claim_lease() {
+        let desc = "jjt: Fix bug\nstatus: claimed\npriority: 2\nagent: claude\nlease_until: 2026-02-16T12:00:00+00:00\n";
+        let task = Task::from_description("abc".into(), desc).unwrap();
+        let before: DateTime<Utc> = "2026-02-16T11:00:00+00:00".parse().unwrap();
+        let after: DateTime<Utc> = "2026-02-16T13:00:00+00:00".parse().unwrap();
+        assert!(!task.claim_expired(before));
+        assert!(!task.is_claimable(before));
+        assert!(task.claim_expired(after));
+        assert!(task.is_claimable(after));
+        assert!(task
+            .to_description()
+            .contains("lease_until: 2026-02-16T12:00:00+00:00"));
+    }
 }